@@ -0,0 +1,287 @@
+use linera_sdk::{
+    base::{ChainId, Timestamp, WithContractAbi},
+    views::{RootView, View, ViewStorageContext},
+    Contract, ContractRuntime,
+};
+use tetris_common::{
+    lobby::{
+        self, LobbyAbi, LobbyMessage, LobbyOperation, LobbyParameters, LobbyResponse, LobbyState,
+        QueueEntry, QueueStatus,
+    },
+    rating::RatingOperation,
+    GameAction, GameMode, GameResponse, Operation,
+};
+
+pub struct LobbyContractImpl {
+    state: LobbyState,
+    runtime: ContractRuntime<Self>,
+}
+
+impl WithContractAbi for LobbyContractImpl {
+    type Abi = LobbyAbi;
+}
+
+linera_sdk::contract!(LobbyContractImpl);
+
+impl Contract for LobbyContractImpl {
+    type Message = LobbyMessage;
+    type Parameters = LobbyParameters;
+    type InstantiationArgument = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = LobbyState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state, runtime }
+    }
+
+    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
+        self.runtime
+            .application_parameters()
+            .validate()
+            .expect("Invalid lobby parameters");
+    }
+
+    async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
+        let lobby_chain = self.runtime.application_parameters().lobby_chain;
+
+        match operation {
+            LobbyOperation::JoinQueue { mode, rating } => {
+                if matches!(self.state.status.get(), QueueStatus::Searching { .. }) {
+                    return LobbyResponse {
+                        success: false,
+                        message: "Already in queue".to_string(),
+                    };
+                }
+
+                self.state.status.set(QueueStatus::Searching { mode });
+                self.runtime
+                    .prepare_message(LobbyMessage::JoinQueue { mode, rating })
+                    .send_to(lobby_chain);
+
+                LobbyResponse {
+                    success: true,
+                    message: "Joined queue".to_string(),
+                }
+            }
+            LobbyOperation::LeaveQueue => {
+                if !matches!(self.state.status.get(), QueueStatus::Searching { .. }) {
+                    return LobbyResponse {
+                        success: false,
+                        message: "Not in queue".to_string(),
+                    };
+                }
+
+                self.state.status.set(QueueStatus::Idle);
+                self.runtime
+                    .prepare_message(LobbyMessage::LeaveQueue)
+                    .send_to(lobby_chain);
+
+                LobbyResponse {
+                    success: true,
+                    message: "Left queue".to_string(),
+                }
+            }
+            LobbyOperation::ExpireQueue => {
+                self.runtime
+                    .prepare_message(LobbyMessage::ExpireQueue)
+                    .send_to(lobby_chain);
+
+                LobbyResponse {
+                    success: true,
+                    message: "Queue expiry requested".to_string(),
+                }
+            }
+        }
+    }
+
+    async fn execute_message(&mut self, message: Self::Message) {
+        let parameters = self.runtime.application_parameters();
+        let sender = self
+            .runtime
+            .message_id()
+            .expect("Message must have an ID")
+            .chain_id;
+        let on_lobby_chain = self.runtime.chain_id() == parameters.lobby_chain;
+
+        match message {
+            LobbyMessage::JoinQueue { mode, rating } => {
+                if on_lobby_chain {
+                    self.join_queue(&parameters, sender, mode, rating);
+                }
+            }
+            LobbyMessage::LeaveQueue => {
+                if on_lobby_chain {
+                    let mut queue = self.state.queue.get().clone();
+                    queue.retain(|entry| entry.chain_id != sender);
+                    let now = self.runtime.system_time();
+                    self.expire_queue(&parameters, &mut queue, now);
+                    self.state.queue.set(queue);
+                }
+            }
+            LobbyMessage::ExpireQueue => {
+                if on_lobby_chain {
+                    let mut queue = self.state.queue.get().clone();
+                    let now = self.runtime.system_time();
+                    self.expire_queue(&parameters, &mut queue, now);
+                    self.state.queue.set(queue);
+                }
+            }
+            LobbyMessage::MatchFound {
+                opponent_chain,
+                seed,
+                match_id,
+            } => {
+                // Приймаємо результат підбору лише від ланцюжка лобі
+                if sender != parameters.lobby_chain {
+                    return;
+                }
+                // Суперник уже встиг скасувати цей матч
+                if *self.state.status.get() == (QueueStatus::MatchCancelled { match_id }) {
+                    return;
+                }
+
+                // Автентифікований виклик: застосунок гри приймає матчі лише від лобі
+                let response: GameResponse = self.runtime.call_application(
+                    true,
+                    parameters.tetris_application,
+                    &Operation {
+                        action: GameAction::MatchFound {
+                            opponent_chain,
+                            seed,
+                            match_id,
+                        },
+                    },
+                );
+                if response.success {
+                    self.state.status.set(QueueStatus::Matched {
+                        opponent_chain,
+                        match_id,
+                    });
+                } else {
+                    // Гра не почалася (наприклад, триває інша гра): суперник не чекатиме
+                    self.state
+                        .status
+                        .set(QueueStatus::MatchCancelled { match_id });
+                    self.runtime
+                        .prepare_message(LobbyMessage::MatchCancelled { match_id })
+                        .send_to(opponent_chain);
+                }
+            }
+            LobbyMessage::MatchCancelled { match_id } => {
+                let matched = QueueStatus::Matched {
+                    opponent_chain: sender,
+                    match_id,
+                };
+                let status = *self.state.status.get();
+                if status != matched && !matches!(status, QueueStatus::Searching { .. }) {
+                    return;
+                }
+
+                // Скасування може прийти раніше за сам матч; тоді гру просто не почнемо
+                self.state
+                    .status
+                    .set(QueueStatus::MatchCancelled { match_id });
+                if status != matched {
+                    return;
+                }
+                self.runtime.call_application(
+                    true,
+                    parameters.tetris_application,
+                    &Operation {
+                        action: GameAction::CancelMatch { match_id },
+                    },
+                );
+            }
+            LobbyMessage::QueueTimedOut => {
+                if sender == parameters.lobby_chain
+                    && matches!(self.state.status.get(), QueueStatus::Searching { .. })
+                {
+                    self.state.status.set(QueueStatus::TimedOut);
+                }
+            }
+        }
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl LobbyContractImpl {
+    fn join_queue(
+        &mut self,
+        parameters: &LobbyParameters,
+        player_chain: ChainId,
+        mode: GameMode,
+        rating: u32,
+    ) {
+        let now = self.runtime.system_time();
+        let mut queue = self.state.queue.get().clone();
+
+        // Спочатку прибираємо гравців, які чекали занадто довго
+        self.expire_queue(parameters, &mut queue, now);
+
+        // Повторний запит замінює попередній запис гравця
+        queue.retain(|entry| entry.chain_id != player_chain);
+
+        let player = QueueEntry {
+            chain_id: player_chain,
+            mode,
+            rating,
+            joined_at: now,
+        };
+
+        match lobby::find_opponent(&queue, &player, parameters.rating_window, now) {
+            Some(index) => {
+                let opponent = queue.remove(index);
+                let match_id = *self.state.next_match_id.get();
+                self.state.next_match_id.set(match_id + 1);
+
+                if let Some(rating_application) = parameters.rating_application {
+                    self.runtime.call_application(
+                        true,
+                        rating_application,
+                        &RatingOperation::RegisterMatch {
+                            match_id,
+                            players: (player_chain, opponent.chain_id),
+                        },
+                    );
+                }
+
+                // Обидва гравці отримують однакове зерно, тож і однакові фігури
+                let seed = lobby::match_seed(match_id, now);
+                self.runtime
+                    .prepare_message(LobbyMessage::MatchFound {
+                        opponent_chain: opponent.chain_id,
+                        seed,
+                        match_id,
+                    })
+                    .send_to(player_chain);
+                self.runtime
+                    .prepare_message(LobbyMessage::MatchFound {
+                        opponent_chain: player_chain,
+                        seed,
+                        match_id,
+                    })
+                    .send_to(opponent.chain_id);
+            }
+            None => queue.push(player),
+        }
+
+        self.state.queue.set(queue);
+    }
+
+    fn expire_queue(
+        &mut self,
+        parameters: &LobbyParameters,
+        queue: &mut Vec<QueueEntry>,
+        now: Timestamp,
+    ) {
+        for expired in lobby::expire_entries(queue, now, parameters.queue_timeout_micros) {
+            self.runtime
+                .prepare_message(LobbyMessage::QueueTimedOut)
+                .send_to(expired.chain_id);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use linera_sdk::{
+    base::WithServiceAbi,
+    views::{View, ViewStorageContext},
+    Service, ServiceRuntime,
+};
+use tetris_common::lobby::{LobbyAbi, LobbyParameters, LobbySnapshot, LobbyState};
+
+pub struct LobbyServiceImpl {
+    state: LobbyState,
+}
+
+impl WithServiceAbi for LobbyServiceImpl {
+    type Abi = LobbyAbi;
+}
+
+linera_sdk::service!(LobbyServiceImpl);
+
+impl Service for LobbyServiceImpl {
+    type Parameters = LobbyParameters;
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = LobbyState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state }
+    }
+
+    async fn handle_query(&self, _query: Self::Query) -> Self::QueryResponse {
+        LobbySnapshot {
+            status: *self.state.status.get(),
+            queue: self.state.queue.get().clone(),
+        }
+    }
+}
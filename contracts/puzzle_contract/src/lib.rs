@@ -0,0 +1,126 @@
+use linera_sdk::{
+    base::WithContractAbi,
+    views::{RootView, View, ViewStorageContext},
+    Contract, ContractRuntime,
+};
+use tetris_common::puzzle::{
+    PublishedPuzzle, PuzzleAbi, PuzzleMessage, PuzzleOperation, PuzzleParameters, PuzzleResponse,
+    PuzzleState,
+};
+
+pub struct PuzzleContractImpl {
+    state: PuzzleState,
+    runtime: ContractRuntime<Self>,
+}
+
+impl WithContractAbi for PuzzleContractImpl {
+    type Abi = PuzzleAbi;
+}
+
+linera_sdk::contract!(PuzzleContractImpl);
+
+impl Contract for PuzzleContractImpl {
+    type Message = PuzzleMessage;
+    type Parameters = PuzzleParameters;
+    type InstantiationArgument = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = PuzzleState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state, runtime }
+    }
+
+    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {}
+
+    async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
+        let parameters = self.runtime.application_parameters();
+
+        // Усі дії пересилаються на ланцюжок головоломок від імені цього ланцюжка
+        let message = match operation {
+            PuzzleOperation::Publish(puzzle) => {
+                if let Err(error) = puzzle.validate() {
+                    return PuzzleResponse {
+                        success: false,
+                        message: error.to_string(),
+                    };
+                }
+                PuzzleMessage::Publish(puzzle)
+            }
+            PuzzleOperation::SubmitAttempt { puzzle_id, replay } => {
+                PuzzleMessage::SubmitAttempt { puzzle_id, replay }
+            }
+        };
+
+        self.runtime
+            .prepare_message(message)
+            .send_to(parameters.puzzle_chain);
+
+        PuzzleResponse {
+            success: true,
+            message: "Request sent".to_string(),
+        }
+    }
+
+    async fn execute_message(&mut self, message: Self::Message) {
+        let parameters = self.runtime.application_parameters();
+        if self.runtime.chain_id() != parameters.puzzle_chain {
+            return;
+        }
+
+        let sender = self
+            .runtime
+            .message_id()
+            .expect("Message must have an ID")
+            .chain_id;
+
+        match message {
+            PuzzleMessage::Publish(puzzle) => {
+                if puzzle.validate().is_err() {
+                    return;
+                }
+                let id = *self.state.next_puzzle_id.get();
+                self.state.next_puzzle_id.set(id + 1);
+                let published =
+                    PublishedPuzzle::new(id, sender, self.runtime.system_time(), puzzle);
+                self.state
+                    .puzzles
+                    .insert(&id, published)
+                    .expect("Failed to store puzzle");
+            }
+            PuzzleMessage::SubmitAttempt { puzzle_id, replay } => {
+                let best = self
+                    .state
+                    .solutions
+                    .get(&(puzzle_id, sender))
+                    .await
+                    .expect("Failed to read solution");
+                let Some(published) = self
+                    .state
+                    .puzzles
+                    .get_mut(&puzzle_id)
+                    .await
+                    .expect("Failed to read puzzle")
+                else {
+                    return;
+                };
+
+                // Записи, що не відтворюються на цій головоломці, ігноруються
+                let Some(attempt) = published.puzzle.attempt(&replay) else {
+                    return;
+                };
+                published.record_attempt(&attempt, best.is_none());
+                if attempt.solved && best.is_none_or(|fewest| attempt.pieces_used < fewest) {
+                    self.state
+                        .solutions
+                        .insert(&(puzzle_id, sender), attempt.pieces_used)
+                        .expect("Failed to store solution");
+                }
+            }
+        }
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
@@ -0,0 +1,62 @@
+use linera_sdk::{
+    base::WithServiceAbi,
+    views::{View, ViewStorageContext},
+    Service, ServiceRuntime,
+};
+use tetris_common::puzzle::{
+    PuzzleAbi, PuzzleParameters, PuzzleQuery, PuzzleQueryResponse, PuzzleState,
+};
+
+pub struct PuzzleServiceImpl {
+    state: PuzzleState,
+}
+
+impl WithServiceAbi for PuzzleServiceImpl {
+    type Abi = PuzzleAbi;
+}
+
+linera_sdk::service!(PuzzleServiceImpl);
+
+impl Service for PuzzleServiceImpl {
+    type Parameters = PuzzleParameters;
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = PuzzleState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state }
+    }
+
+    async fn handle_query(&self, query: Self::Query) -> Self::QueryResponse {
+        match query {
+            // Головоломки разом зі статистикою спроб і розв'язків
+            PuzzleQuery::Puzzles => {
+                let mut puzzles = Vec::new();
+                self.state
+                    .puzzles
+                    .for_each_index_value(|_, puzzle| {
+                        puzzles.push(puzzle);
+                        Ok(())
+                    })
+                    .await
+                    .expect("Failed to read puzzles");
+                PuzzleQueryResponse::Puzzles(puzzles)
+            }
+            PuzzleQuery::Puzzle { puzzle_id } => PuzzleQueryResponse::Puzzle(
+                self.state
+                    .puzzles
+                    .get(&puzzle_id)
+                    .await
+                    .expect("Failed to read puzzle")
+                    .map(Box::new),
+            ),
+            PuzzleQuery::Solution { puzzle_id, player } => PuzzleQueryResponse::Solution(
+                self.state
+                    .solutions
+                    .get(&(puzzle_id, player))
+                    .await
+                    .expect("Failed to read solution"),
+            ),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use linera_sdk::{
+    base::{ChainId, WithContractAbi},
+    views::{RootView, View, ViewStorageContext},
+    Contract, ContractRuntime,
+};
+use tetris_common::rating::{
+    self, MatchReport, MatchResult, ModeRecord, PlayerRating, RatingAbi, RatingChange,
+    RatingMessage, RatingOperation, RatingParameters, RatingState,
+};
+
+pub struct RatingContractImpl {
+    state: RatingState,
+    runtime: ContractRuntime<Self>,
+}
+
+impl WithContractAbi for RatingContractImpl {
+    type Abi = RatingAbi;
+}
+
+linera_sdk::contract!(RatingContractImpl);
+
+impl Contract for RatingContractImpl {
+    type Message = RatingMessage;
+    type Parameters = RatingParameters;
+    type InstantiationArgument = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = RatingState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state, runtime }
+    }
+
+    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
+        // Таблиці рекордів ведуться лише за коректними правилами
+        self.runtime
+            .application_parameters()
+            .rules
+            .validate()
+            .expect("Invalid rule set");
+    }
+
+    async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
+        // Пересилаємо звіт на ланцюжок рейтингу від імені цього ланцюжка
+        let message = match operation {
+            RatingOperation::RegisterMatch { match_id, players } => {
+                // Матчі реєструє лише застосунок з кодом лобі
+                let lobby_bytecode = self.runtime.application_parameters().lobby_bytecode;
+                let caller = self.runtime.authenticated_caller_id();
+                if lobby_bytecode.is_none_or(|bytecode| {
+                    caller.is_none_or(|caller| caller.bytecode_id != bytecode.forget_abi())
+                }) {
+                    return;
+                }
+                RatingMessage::RegisterMatch { match_id, players }
+            }
+            RatingOperation::ReportResult(result) => RatingMessage::ReportResult(result),
+            RatingOperation::SubmitRecord { replay } => {
+                // Лише застосунок гри гарантує, що запис зіграно на цьому ланцюжку
+                let tetris_bytecode = self.runtime.application_parameters().tetris_bytecode;
+                let caller = self.runtime.authenticated_caller_id();
+                if caller.is_none_or(|caller| caller.bytecode_id != tetris_bytecode.forget_abi()) {
+                    return;
+                }
+                RatingMessage::SubmitRecord { replay }
+            }
+        };
+        let rating_chain = self.runtime.application_parameters().rating_chain;
+        self.runtime.prepare_message(message).send_to(rating_chain);
+    }
+
+    async fn execute_message(&mut self, message: Self::Message) {
+        if self.runtime.chain_id() != self.runtime.application_parameters().rating_chain {
+            return;
+        }
+
+        let reporter = self
+            .runtime
+            .message_id()
+            .expect("Message must have an ID")
+            .chain_id;
+
+        match message {
+            RatingMessage::RegisterMatch { match_id, players } => {
+                let registered = self
+                    .state
+                    .matches
+                    .contains_key(&match_id)
+                    .await
+                    .expect("Failed to read match");
+                if !registered {
+                    self.state
+                        .matches
+                        .insert(&match_id, players)
+                        .expect("Failed to store match");
+                }
+            }
+            RatingMessage::ReportResult(result) => self.report_result(result, reporter).await,
+            RatingMessage::SubmitRecord { replay } => self.submit_record(&replay, reporter).await,
+        }
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl RatingContractImpl {
+    async fn submit_record(&mut self, replay: &[u8], player: ChainId) {
+        let rules = self.runtime.application_parameters().rules;
+        // Записи, що не відтворюються за правилами таблиць, ігноруються
+        let Some(record) = ModeRecord::from_replay(replay, &rules, self.runtime.system_time())
+        else {
+            return;
+        };
+
+        let key = (record.mode, record.seed, player);
+        let best = self
+            .state
+            .mode_records
+            .get(&key)
+            .await
+            .expect("Failed to read mode record");
+        if best.is_none_or(|best| record.beats(&best)) {
+            self.state
+                .mode_records
+                .insert(&key, record)
+                .expect("Failed to store mode record");
+        }
+    }
+
+    async fn report_result(&mut self, result: MatchResult, reporter: ChainId) {
+        // Звіт приймається лише від учасників матчу, зареєстрованого лобі
+        if reporter != result.winner && reporter != result.loser {
+            return;
+        }
+        let players = self
+            .state
+            .matches
+            .get(&result.match_id)
+            .await
+            .expect("Failed to read match");
+        if !players.is_some_and(|players| rating::report_matches(players, &result)) {
+            return;
+        }
+
+        let existing = self
+            .state
+            .reports
+            .get(&result.match_id)
+            .await
+            .expect("Failed to read match report");
+        let already_confirmed = matches!(existing, Some(MatchReport::Confirmed(_)));
+        let report = rating::merge_report(existing, result, reporter);
+        self.state
+            .reports
+            .insert(&result.match_id, report)
+            .expect("Failed to store match report");
+
+        if let MatchReport::Confirmed(result) = report {
+            if !already_confirmed {
+                self.apply_result(result).await;
+            }
+        }
+    }
+
+    async fn apply_result(&mut self, result: MatchResult) {
+        let winner = self.player_rating(result.winner).await;
+        let loser = self.player_rating(result.loser).await;
+        let (winner_after, loser_after) = rating::elo_update(winner.rating, loser.rating);
+        let timestamp = self.runtime.system_time();
+
+        self.record(
+            result.winner,
+            PlayerRating {
+                rating: winner_after,
+                games: winner.games + 1,
+                wins: winner.wins + 1,
+                ..winner
+            },
+            RatingChange {
+                match_id: result.match_id,
+                opponent: result.loser,
+                won: true,
+                rating_before: winner.rating,
+                rating_after: winner_after,
+                timestamp,
+            },
+        )
+        .await;
+        self.record(
+            result.loser,
+            PlayerRating {
+                rating: loser_after,
+                games: loser.games + 1,
+                losses: loser.losses + 1,
+                ..loser
+            },
+            RatingChange {
+                match_id: result.match_id,
+                opponent: result.winner,
+                won: false,
+                rating_before: loser.rating,
+                rating_after: loser_after,
+                timestamp,
+            },
+        )
+        .await;
+    }
+
+    async fn player_rating(&self, player: ChainId) -> PlayerRating {
+        self.state
+            .ratings
+            .get(&player)
+            .await
+            .expect("Failed to read rating")
+            .unwrap_or_default()
+    }
+
+    async fn record(&mut self, player: ChainId, rating: PlayerRating, change: RatingChange) {
+        self.state
+            .ratings
+            .insert(&player, rating)
+            .expect("Failed to store rating");
+
+        let history = self
+            .state
+            .history
+            .get_mut_or_default(&player)
+            .await
+            .expect("Failed to read rating history");
+        history.push(change);
+    }
+}
@@ -0,0 +1,92 @@
+use linera_sdk::{
+    base::WithServiceAbi,
+    views::{View, ViewStorageContext},
+    Service, ServiceRuntime,
+};
+use tetris_common::{
+    rating::{RatingAbi, RatingParameters, RatingQuery, RatingQueryResponse, RatingState},
+    GameMode,
+};
+
+pub struct RatingServiceImpl {
+    state: RatingState,
+}
+
+impl WithServiceAbi for RatingServiceImpl {
+    type Abi = RatingAbi;
+}
+
+linera_sdk::service!(RatingServiceImpl);
+
+impl Service for RatingServiceImpl {
+    type Parameters = RatingParameters;
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = RatingState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state }
+    }
+
+    async fn handle_query(&self, query: Self::Query) -> Self::QueryResponse {
+        match query {
+            RatingQuery::Leaderboard { limit } => {
+                let mut leaderboard = Vec::new();
+                self.state
+                    .ratings
+                    .for_each_index_value(|chain_id, rating| {
+                        leaderboard.push((chain_id, rating));
+                        Ok(())
+                    })
+                    .await
+                    .expect("Failed to read ratings");
+                leaderboard.sort_by_key(|(_, rating)| std::cmp::Reverse(rating.rating));
+                leaderboard.truncate(limit as usize);
+                RatingQueryResponse::Leaderboard(leaderboard)
+            }
+            RatingQuery::ModeLeaderboard { mode, seed, limit } => {
+                let mut leaderboard = Vec::new();
+                self.state
+                    .mode_records
+                    .for_each_index_value(|(record_mode, record_seed, chain_id), record| {
+                        if record_mode == mode && record_seed == seed {
+                            leaderboard.push((chain_id, record));
+                        }
+                        Ok(())
+                    })
+                    .await
+                    .expect("Failed to read mode records");
+                // Розкопки - за зростанням часу, виживання - за спаданням
+                leaderboard.sort_by_key(|(_, record)| record.elapsed_micros);
+                if mode == GameMode::Survival {
+                    leaderboard.reverse();
+                }
+                leaderboard.truncate(limit as usize);
+                RatingQueryResponse::ModeLeaderboard(leaderboard)
+            }
+            RatingQuery::Player { chain_id } => RatingQueryResponse::Player(
+                self.state
+                    .ratings
+                    .get(&chain_id)
+                    .await
+                    .expect("Failed to read rating")
+                    .unwrap_or_default(),
+            ),
+            RatingQuery::History { chain_id } => RatingQueryResponse::History(
+                self.state
+                    .history
+                    .get(&chain_id)
+                    .await
+                    .expect("Failed to read rating history")
+                    .unwrap_or_default(),
+            ),
+            RatingQuery::Match { match_id } => RatingQueryResponse::Match(
+                self.state
+                    .reports
+                    .get(&match_id)
+                    .await
+                    .expect("Failed to read match report"),
+            ),
+        }
+    }
+}
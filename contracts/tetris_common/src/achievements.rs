@@ -0,0 +1,296 @@
+use linera_sdk::base::{BlockHeight, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::{GameMode, GameState, VersusResult};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AchievementCondition {
+    // Одним скиданням очищено щонайменше стільки рядків
+    LinesAtOnce {
+        lines: u32,
+    },
+    // T-спіни за всі ігри
+    TSpins {
+        count: u32,
+    },
+    PerfectClears {
+        count: u32,
+    },
+    LinesInGame {
+        mode: GameMode,
+        lines: u32,
+    },
+    ScoreInGame {
+        mode: GameMode,
+        score: u32,
+    },
+    // Рядки в марафоні, очищені швидше за заданий час від початку гри
+    Sprint {
+        lines: u32,
+        max_duration_micros: u64,
+    },
+    GamesPlayed {
+        count: u32,
+    },
+    VersusWins {
+        count: u32,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AchievementDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: AchievementCondition,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EarnedAchievement {
+    pub id: String,
+    pub block_height: BlockHeight,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AchievementStatus {
+    pub definition: AchievementDefinition,
+    pub earned: Option<EarnedAchievement>,
+}
+
+// Лічильники гравця, що накопичуються між іграми
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct AchievementProgress {
+    pub t_spins: u32,
+    pub perfect_clears: u32,
+    pub games_played: u32,
+    pub versus_wins: u32,
+}
+
+pub struct AchievementContext<'a> {
+    pub state: &'a GameState,
+    // Рядки, очищені останнім скиданням
+    pub lines_cleared: u32,
+    pub elapsed_micros: u64,
+    pub progress: &'a AchievementProgress,
+}
+
+impl AchievementProgress {
+    pub fn record_game(&mut self, state: &GameState) {
+        self.games_played += 1;
+        if state.versus_result == Some(VersusResult::Won) {
+            self.versus_wins += 1;
+        }
+    }
+}
+
+impl AchievementCondition {
+    pub fn is_met(&self, context: &AchievementContext) -> bool {
+        let state = context.state;
+        match *self {
+            AchievementCondition::LinesAtOnce { lines } => context.lines_cleared >= lines,
+            AchievementCondition::TSpins { count } => context.progress.t_spins >= count,
+            AchievementCondition::PerfectClears { count } => {
+                context.progress.perfect_clears >= count
+            }
+            AchievementCondition::LinesInGame { mode, lines } => {
+                state.mode == mode && state.lines >= lines
+            }
+            AchievementCondition::ScoreInGame { mode, score } => {
+                state.mode == mode && state.score >= score
+            }
+            AchievementCondition::Sprint {
+                lines,
+                max_duration_micros,
+            } => {
+                state.mode == GameMode::Marathon
+                    && state.lines >= lines
+                    && context.elapsed_micros <= max_duration_micros
+            }
+            AchievementCondition::GamesPlayed { count } => context.progress.games_played >= count,
+            AchievementCondition::VersusWins { count } => context.progress.versus_wins >= count,
+        }
+    }
+}
+
+// Таблиця досягнень: нове досягнення додається ще одним рядком
+pub fn definitions() -> Vec<AchievementDefinition> {
+    use AchievementCondition::*;
+
+    [
+        (
+            "first_tetris",
+            "Tetris!",
+            "Clear four lines with one piece",
+            LinesAtOnce { lines: 4 },
+        ),
+        (
+            "t_spin_10",
+            "Spin Doctor",
+            "Perform 10 T-spins",
+            TSpins { count: 10 },
+        ),
+        (
+            "perfect_clear",
+            "Clean Sweep",
+            "Clear the whole board",
+            PerfectClears { count: 1 },
+        ),
+        (
+            "marathon_100",
+            "Marathon Runner",
+            "Clear 100 lines in one Marathon game",
+            LinesInGame {
+                mode: GameMode::Marathon,
+                lines: 100,
+            },
+        ),
+        (
+            "score_10000",
+            "High Scorer",
+            "Score 10000 points in one Marathon game",
+            ScoreInGame {
+                mode: GameMode::Marathon,
+                score: 10_000,
+            },
+        ),
+        (
+            "sprint_40",
+            "Sprinter",
+            "Clear 40 lines in under 3 minutes",
+            Sprint {
+                lines: 40,
+                max_duration_micros: 180_000_000,
+            },
+        ),
+        (
+            "games_100",
+            "Regular",
+            "Finish 100 games",
+            GamesPlayed { count: 100 },
+        ),
+        (
+            "first_win",
+            "First Blood",
+            "Win a versus game",
+            VersusWins { count: 1 },
+        ),
+        (
+            "wins_50",
+            "Champion",
+            "Win 50 versus games",
+            VersusWins { count: 50 },
+        ),
+    ]
+    .into_iter()
+    .map(|(id, name, description, condition)| AchievementDefinition {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        condition,
+    })
+    .collect()
+}
+
+// Ідентифікатори досягнень, умови яких виконано
+pub fn met_achievements(context: &AchievementContext) -> Vec<String> {
+    definitions()
+        .into_iter()
+        .filter(|definition| definition.condition.is_met(context))
+        .map(|definition| definition.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    fn met(state: &GameState, lines_cleared: u32, progress: &AchievementProgress) -> Vec<String> {
+        met_achievements(&AchievementContext {
+            state,
+            lines_cleared,
+            elapsed_micros: 200_000_000,
+            progress,
+        })
+    }
+
+    #[test]
+    fn definitions_have_unique_ids() {
+        let definitions = definitions();
+        let mut ids: Vec<&str> = definitions.iter().map(|item| item.id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), definitions.len());
+    }
+
+    #[test]
+    fn game_conditions_unlock() {
+        let mut state = GameState::new(RuleSet::default());
+        let progress = AchievementProgress::default();
+        assert!(met(&state, 3, &progress).is_empty());
+        assert_eq!(met(&state, 4, &progress), vec!["first_tetris"]);
+
+        state.lines = 100;
+        state.score = 10_000;
+        assert_eq!(
+            met(&state, 0, &progress),
+            vec!["marathon_100", "score_10000"]
+        );
+        // Рекорди за гру зараховуються лише у своєму режимі
+        state.mode = GameMode::Versus;
+        assert!(met(&state, 0, &progress).is_empty());
+    }
+
+    #[test]
+    fn sprint_needs_lines_in_time() {
+        let state = GameState {
+            lines: 40,
+            ..GameState::new(RuleSet::default())
+        };
+        let progress = AchievementProgress::default();
+        let sprint = AchievementCondition::Sprint {
+            lines: 40,
+            max_duration_micros: 180_000_000,
+        };
+        let context = |elapsed_micros| AchievementContext {
+            state: &state,
+            lines_cleared: 0,
+            elapsed_micros,
+            progress: &progress,
+        };
+        assert!(sprint.is_met(&context(180_000_000)));
+        assert!(!sprint.is_met(&context(180_000_001)));
+    }
+
+    #[test]
+    fn progress_accumulates_between_games() {
+        let mut state = GameState::new(RuleSet::default());
+        let mut progress = AchievementProgress {
+            t_spins: 9,
+            perfect_clears: 1,
+            ..AchievementProgress::default()
+        };
+        assert_eq!(met(&state, 0, &progress), vec!["perfect_clear"]);
+
+        progress.t_spins += 1;
+        state.versus_result = Some(VersusResult::Won);
+        for _ in 0..100 {
+            progress.record_game(&state);
+        }
+        state.versus_result = Some(VersusResult::Lost);
+        progress.record_game(&state);
+        assert_eq!(progress.games_played, 101);
+        assert_eq!(progress.versus_wins, 100);
+        assert_eq!(
+            met(&state, 0, &progress),
+            vec![
+                "t_spin_10",
+                "perfect_clear",
+                "games_100",
+                "first_win",
+                "wins_50"
+            ]
+        );
+    }
+}
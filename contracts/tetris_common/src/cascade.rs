@@ -0,0 +1,160 @@
+use crate::{GameState, PieceType};
+
+type Board = Vec<Vec<Option<PieceType>>>;
+
+// Групи зайнятих клітинок, з'єднаних сторонами
+fn components(board: &Board) -> Vec<Vec<(usize, usize)>> {
+    let mut seen = vec![vec![false; board[0].len()]; board.len()];
+    let mut components = Vec::new();
+
+    for y in 0..board.len() {
+        for x in 0..board[y].len() {
+            if seen[y][x] || board[y][x].is_none() {
+                continue;
+            }
+            seen[y][x] = true;
+            let mut component = vec![(x, y)];
+            let mut index = 0;
+            while index < component.len() {
+                let (cx, cy) = component[index];
+                let neighbours = [
+                    (cx.wrapping_sub(1), cy),
+                    (cx + 1, cy),
+                    (cx, cy.wrapping_sub(1)),
+                    (cx, cy + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    let occupied = board
+                        .get(ny)
+                        .and_then(|row| row.get(nx))
+                        .is_some_and(Option::is_some);
+                    if occupied && !seen[ny][nx] {
+                        seen[ny][nx] = true;
+                        component.push((nx, ny));
+                    }
+                }
+                index += 1;
+            }
+            components.push(component);
+        }
+    }
+    components
+}
+
+// Опускає групу клітинок, доки вона не ляже на дно або на інші клітинки;
+// повертає true, якщо група зрушила
+fn drop_component(state: &mut GameState, component: &[(usize, usize)]) -> bool {
+    let board = &mut state.board;
+    let cells: Vec<_> = component
+        .iter()
+        .map(|&(x, y)| (x, y, board[y][x].take()))
+        .collect();
+
+    let mut distance = 0;
+    while cells.iter().all(|&(x, y, _)| {
+        board
+            .get(y + distance + 1)
+            .is_some_and(|row| row[x].is_none())
+    }) {
+        distance += 1;
+    }
+    for &(x, y, cell) in &cells {
+        board[y + distance][x] = cell;
+    }
+    // Номери фігур переїжджають разом із клітинками; знизу вгору, щоб не затерти ще
+    // не перенесені
+    if let Some(stamps) = &mut state.lock_stamps {
+        let mut moved: Vec<_> = cells.iter().map(|&(x, y, _)| (x, y)).collect();
+        moved.sort_by_key(|&(_, y)| std::cmp::Reverse(y));
+        for (x, y) in moved {
+            stamps[y + distance][x] = stamps[y][x];
+        }
+    }
+    distance > 0
+}
+
+// Каскадна гравітація: незв'язані між собою групи клітинок падають, доки всі не ляжуть.
+// Нижні групи падають першими, тож верхні лягають уже на їхнє нове місце.
+pub fn settle(state: &mut GameState) {
+    loop {
+        let mut components = components(&state.board);
+        components
+            .sort_by_key(|component| std::cmp::Reverse(component.iter().map(|&(_, y)| y).max()));
+
+        let mut moved = false;
+        for component in &components {
+            moved |= drop_component(state, component);
+        }
+        // Групи, що лягли одна на одну, злипаються, тож перераховуємо їх заново
+        if !moved {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine,
+        rules::{ClearGravity, RuleSet},
+    };
+
+    // Нижній рядок заповнено, над ним рядок без правої клітинки,
+    // а вище окремо висить одна клітинка над отвором
+    fn chain_state(clear_gravity: ClearGravity) -> GameState {
+        let mut state = GameState::new(RuleSet {
+            width: 4,
+            height: 6,
+            clear_gravity,
+            ..RuleSet::default()
+        });
+        state.board[2][3] = Some(PieceType::T);
+        for x in 0..3 {
+            state.board[4][x] = Some(PieceType::O);
+        }
+        state.board[5] = vec![Some(PieceType::I); 4];
+        state
+    }
+
+    #[test]
+    fn cascades_trigger_chains() {
+        let mut state = chain_state(ClearGravity::Naive);
+        assert_eq!(engine::clear_lines(&mut state), 1);
+        assert_eq!(state.board[3][3], Some(PieceType::T));
+        assert_eq!(state.score, 100);
+
+        // Клітинка падає в отвір і заповнює ще один рядок; друга ланка коштує вдвічі більше
+        let mut state = chain_state(ClearGravity::Cascade);
+        assert_eq!(engine::clear_lines(&mut state), 2);
+        assert!(state.board.iter().flatten().all(Option::is_none));
+        assert_eq!(state.score, 300);
+        assert_eq!(state.lines, 2);
+    }
+
+    #[test]
+    fn connected_blocks_fall_together() {
+        let mut state = GameState::new(RuleSet {
+            width: 4,
+            height: 5,
+            ..RuleSet::default()
+        });
+        let board = &mut state.board;
+        // Г-подібна група спирається на стовпчик лише одним кінцем і не падає
+        board[1][0] = Some(PieceType::L);
+        board[1][1] = Some(PieceType::L);
+        board[2][1] = Some(PieceType::L);
+        board[3][1] = Some(PieceType::J);
+        board[4][1] = Some(PieceType::J);
+        // Окрема пара клітинок падає на дно
+        board[0][3] = Some(PieceType::S);
+        board[1][3] = Some(PieceType::S);
+        settle(&mut state);
+
+        let board = &state.board;
+        assert_eq!(board[1][0], Some(PieceType::L));
+        assert_eq!(board[3][3], Some(PieceType::S));
+        assert_eq!(board[4][3], Some(PieceType::S));
+        assert_eq!(board[0][3], None);
+    }
+}
@@ -0,0 +1,205 @@
+use linera_sdk::base::{ChainId, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    replay::{self, Replay},
+    rules::RuleSet,
+    GameMode,
+};
+
+// Скільки виклик чекає на результати гравців
+pub const CHALLENGE_EXPIRY_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+
+// Виклик однозначно визначається ланцюжком ініціатора та його номером
+pub type ChallengeKey = (ChainId, u64);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ChallengeOutcome {
+    Won,
+    Lost,
+    Draw,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Challenge {
+    pub challenger: ChainId,
+    pub id: u64,
+    pub opponent: ChainId,
+    pub mode: GameMode,
+    pub seed: u64,
+    pub rules: RuleSet,
+    pub challenger_score: Option<u32>,
+    pub opponent_score: Option<u32>,
+    pub declined: bool,
+    pub created_at: Timestamp,
+}
+
+impl Challenge {
+    pub fn key(&self) -> ChallengeKey {
+        (self.challenger, self.id)
+    }
+
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        now.delta_since(self.created_at).as_micros() > CHALLENGE_EXPIRY_MICROS
+    }
+
+    pub fn is_participant(&self, chain_id: ChainId) -> bool {
+        chain_id == self.challenger || chain_id == self.opponent
+    }
+
+    pub fn other(&self, chain_id: ChainId) -> ChainId {
+        if chain_id == self.challenger {
+            self.opponent
+        } else {
+            self.challenger
+        }
+    }
+
+    pub fn score_of(&self, chain_id: ChainId) -> Option<u32> {
+        if chain_id == self.challenger {
+            self.challenger_score
+        } else {
+            self.opponent_score
+        }
+    }
+
+    pub fn record_score(&mut self, chain_id: ChainId, score: u32) {
+        if chain_id == self.challenger {
+            self.challenger_score = Some(score);
+        } else if chain_id == self.opponent {
+            self.opponent_score = Some(score);
+        }
+    }
+
+    // Результат очної зустрічі з погляду гравця, коли обидва зіграли
+    pub fn outcome(&self, chain_id: ChainId) -> Option<ChallengeOutcome> {
+        let mine = self.score_of(chain_id)?;
+        let theirs = self.score_of(self.other(chain_id))?;
+        Some(match mine.cmp(&theirs) {
+            std::cmp::Ordering::Greater => ChallengeOutcome::Won,
+            std::cmp::Ordering::Less => ChallengeOutcome::Lost,
+            std::cmp::Ordering::Equal => ChallengeOutcome::Draw,
+        })
+    }
+
+    // Рахунок суперника визначаємо повторною симуляцією його запису
+    pub fn verify_result(&self, replay_bytes: &[u8], now: Timestamp) -> Result<u32, &'static str> {
+        if self.declined {
+            return Err("Challenge was declined");
+        }
+        if self.is_expired(now) {
+            return Err("Challenge has expired");
+        }
+        let replay = Replay::from_bytes(replay_bytes).map_err(|_| "Invalid replay")?;
+        if replay.seed != self.seed || replay.mode != self.mode || replay.rules != self.rules {
+            return Err("Replay does not match challenge");
+        }
+        replay::simulate(&replay)
+            .map(|state| state.score)
+            .map_err(|_| "Replay verification failed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameAction;
+
+    const CREATED: u64 = 1_000_000;
+
+    fn challenge() -> Challenge {
+        Challenge {
+            challenger: ChainId::root(1),
+            id: 0,
+            opponent: ChainId::root(2),
+            mode: GameMode::Marathon,
+            seed: 42,
+            rules: RuleSet::default(),
+            challenger_score: None,
+            opponent_score: None,
+            declined: false,
+            created_at: Timestamp::from(CREATED),
+        }
+    }
+
+    fn replay(seed: u64, mode: GameMode, rules: RuleSet) -> Vec<u8> {
+        let mut replay = Replay::new(seed, mode, rules);
+        replay.record_action(CREATED, GameAction::Drop);
+        replay.to_bytes()
+    }
+
+    fn now() -> Timestamp {
+        Timestamp::from(CREATED + 60_000_000)
+    }
+
+    #[test]
+    fn matching_replay_is_scored() {
+        let bytes = replay(42, GameMode::Marathon, RuleSet::default());
+        let expected = replay::simulate(&Replay::from_bytes(&bytes).unwrap())
+            .unwrap()
+            .score;
+        assert_eq!(challenge().verify_result(&bytes, now()), Ok(expected));
+        assert_eq!(
+            challenge().verify_result(&[0xff], now()),
+            Err("Invalid replay")
+        );
+    }
+
+    #[test]
+    fn replay_must_match_challenge() {
+        let mismatch = Err("Replay does not match challenge");
+        let challenge = challenge();
+        let wrong_seed = replay(43, GameMode::Marathon, RuleSet::default());
+        assert_eq!(challenge.verify_result(&wrong_seed, now()), mismatch);
+        let wrong_mode = replay(42, GameMode::Survival, RuleSet::default());
+        assert_eq!(challenge.verify_result(&wrong_mode, now()), mismatch);
+        let wrong_rules = replay(
+            42,
+            GameMode::Marathon,
+            RuleSet {
+                hold: !RuleSet::default().hold,
+                ..RuleSet::default()
+            },
+        );
+        assert_eq!(challenge.verify_result(&wrong_rules, now()), mismatch);
+    }
+
+    #[test]
+    fn closed_challenges_take_no_results() {
+        let bytes = replay(42, GameMode::Marathon, RuleSet::default());
+        let declined = Challenge {
+            declined: true,
+            ..challenge()
+        };
+        assert_eq!(
+            declined.verify_result(&bytes, now()),
+            Err("Challenge was declined")
+        );
+
+        let deadline = Timestamp::from(CREATED + CHALLENGE_EXPIRY_MICROS);
+        assert!(challenge().verify_result(&bytes, deadline).is_ok());
+        assert_eq!(
+            challenge().verify_result(&bytes, deadline.saturating_add_micros(1)),
+            Err("Challenge has expired")
+        );
+    }
+
+    #[test]
+    fn outcome_needs_both_scores() {
+        let mut challenge = challenge();
+        challenge.record_score(ChainId::root(1), 300);
+        assert_eq!(challenge.outcome(ChainId::root(1)), None);
+
+        challenge.record_score(ChainId::root(2), 200);
+        // Сторонній ланцюжок рахунку не змінює
+        challenge.record_score(ChainId::root(3), 900);
+        assert_eq!(
+            challenge.outcome(ChainId::root(1)),
+            Some(ChallengeOutcome::Won)
+        );
+        assert_eq!(
+            challenge.outcome(ChainId::root(2)),
+            Some(ChallengeOutcome::Lost)
+        );
+    }
+}
@@ -0,0 +1,735 @@
+use crate::{
+    pieces::{PieceDefinition, PieceSet},
+    replay::{Replay, ReplayEntry, ReplayInput},
+    rules::{
+        ClearGravity, LevelSystem, Randomizer, RotationSystem, RuleSet, Scoring, Visibility,
+        MAX_BOARD_SIZE, MIN_BOARD_SIZE,
+    },
+    GameAction, GameMode, GarbageAttack,
+};
+
+// Заголовок: версія (1) + версія правил (2) + seed (8) + режим (1) + ширина (1) + висота (1)
+// + час першого запису (8) + решта правил гри (RULES_LEN); за ним - власні фігури, якщо є
+const HEADER_LEN: usize = 22 + RULES_LEN;
+// Поворот (1) + генератор (1) + очки за лінії (4 x 4) + множник рівня (1) + система рівнів (1)
+// + стартовий рівень (1) + затримка фіксації (8) + скидання фіксації (4) + відкладання (1)
+// + перегляд (1) + бомби (2) + набір фігур (1) + бонуси за perfect clear (4 x 4 + 4)
+// + рядки розкопок (1) + інтервал сміття (8) + гравітація після очищення (1)
+// + видимість поля та її параметр (2)
+const RULES_LEN: usize = 70;
+
+// Коди дій у потоці по 4 біти
+const OP_MOVE_LEFT: u8 = 0;
+const OP_MOVE_RIGHT: u8 = 1;
+const OP_ROTATE: u8 = 2;
+const OP_DROP: u8 = 3;
+const OP_GAME_OVER: u8 = 4;
+const OP_GARBAGE: u8 = 5;
+// Повтор попереднього руху: наступний ніббл містить кількість повторів мінус 2
+const OP_REPEAT: u8 = 6;
+const OP_PAUSE: u8 = 7;
+const OP_RESUME: u8 = 8;
+// Зсув часу для всіх наступних записів
+const OP_TIME: u8 = 9;
+const OP_HOLD: u8 = 10;
+const OP_END: u8 = 0xF;
+
+const MIN_REPEAT: usize = 2;
+const MAX_REPEAT: usize = MIN_REPEAT + 0xF;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CodecError {
+    UnsupportedVersion(u8),
+    UnsupportedBoard { width: u8, height: u8 },
+    UnknownMode(u8),
+    InvalidRules,
+    UnknownOpcode(u8),
+    // Дію не можна записати у компактному форматі
+    UnsupportedAction,
+    // Час записів має не зменшуватися
+    TimestampOrder,
+    // Приріст часу виводить момент дії за межі u64
+    TimestampOverflow,
+    Truncated,
+}
+
+struct NibbleWriter {
+    bytes: Vec<u8>,
+    high: bool,
+}
+
+impl NibbleWriter {
+    fn push(&mut self, nibble: u8) {
+        if self.high {
+            *self.bytes.last_mut().unwrap() |= nibble << 4;
+        } else {
+            self.bytes.push(nibble & 0xF);
+        }
+        self.high = !self.high;
+    }
+
+    // Число по 3 біти на ніббл; старший біт ніббла означає продовження
+    fn push_varint(&mut self, mut value: u64) {
+        loop {
+            let chunk = (value & 0x7) as u8;
+            value >>= 3;
+            if value == 0 {
+                self.push(chunk);
+                return;
+            }
+            self.push(chunk | 0x8);
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.high {
+            self.push(OP_END);
+        }
+        self.bytes
+    }
+}
+
+struct NibbleReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl NibbleReader<'_> {
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.bytes.get(self.position / 2)?;
+        let nibble = if self.position.is_multiple_of(2) {
+            byte & 0xF
+        } else {
+            byte >> 4
+        };
+        self.position += 1;
+        Some(nibble)
+    }
+
+    fn next_varint(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let nibble = self.next().ok_or(CodecError::Truncated)?;
+            if shift >= 64 {
+                return Err(CodecError::Truncated);
+            }
+            value |= ((nibble & 0x7) as u64) << shift;
+            if nibble & 0x8 == 0 {
+                return Ok(value);
+            }
+            shift += 3;
+        }
+    }
+}
+
+fn mode_code(mode: GameMode) -> u8 {
+    match mode {
+        GameMode::Marathon => 0,
+        GameMode::Versus => 1,
+        GameMode::Royale => 2,
+        GameMode::Puzzle => 3,
+        GameMode::PerfectClearPractice => 4,
+        GameMode::DigRace => 5,
+        GameMode::Survival => 6,
+    }
+}
+
+fn mode_from_code(code: u8) -> Result<GameMode, CodecError> {
+    match code {
+        0 => Ok(GameMode::Marathon),
+        1 => Ok(GameMode::Versus),
+        2 => Ok(GameMode::Royale),
+        3 => Ok(GameMode::Puzzle),
+        4 => Ok(GameMode::PerfectClearPractice),
+        5 => Ok(GameMode::DigRace),
+        6 => Ok(GameMode::Survival),
+        _ => Err(CodecError::UnknownMode(code)),
+    }
+}
+
+fn encode_rules(rules: &RuleSet, bytes: &mut Vec<u8>) {
+    bytes.push(match rules.rotation {
+        RotationSystem::Classic => 0,
+        RotationSystem::Srs => 1,
+        RotationSystem::Ars => 2,
+        RotationSystem::Nes => 3,
+    });
+    bytes.push(match rules.randomizer {
+        Randomizer::Random => 0,
+        Randomizer::SevenBag => 1,
+        Randomizer::Nes => 2,
+    });
+    for points in rules.scoring.line_clear {
+        bytes.extend_from_slice(&points.to_le_bytes());
+    }
+    bytes.push(rules.scoring.level_multiplier as u8);
+    bytes.push(match rules.levels {
+        LevelSystem::Guideline => 0,
+        LevelSystem::Nes => 1,
+    });
+    bytes.push(rules.start_level);
+    bytes.extend_from_slice(&rules.lock_delay_micros.to_le_bytes());
+    bytes.extend_from_slice(&rules.max_lock_resets.to_le_bytes());
+    bytes.push(rules.hold as u8);
+    bytes.push(rules.preview);
+    bytes.extend_from_slice(&rules.bomb_chance.to_le_bytes());
+    bytes.push(match rules.pieces {
+        PieceSet::Tetrominoes => 0,
+        PieceSet::Triominoes => 1,
+        PieceSet::Pentominoes => 2,
+        PieceSet::Custom(_) => 3,
+    });
+    for points in rules.scoring.perfect_clear {
+        bytes.extend_from_slice(&points.to_le_bytes());
+    }
+    bytes.extend_from_slice(&rules.scoring.back_to_back_perfect_clear.to_le_bytes());
+    bytes.push(rules.dig_rows);
+    bytes.extend_from_slice(&rules.garbage_interval_micros.to_le_bytes());
+    bytes.push(match rules.clear_gravity {
+        ClearGravity::Naive => 0,
+        ClearGravity::Cascade => 1,
+    });
+    bytes.extend_from_slice(&match rules.visibility {
+        Visibility::Full => [0, 0],
+        Visibility::Invisible { after_pieces } => [1, after_pieces],
+        Visibility::Fog { rows } => [2, rows],
+    });
+}
+
+// Власні фігури: кількість (1), далі для кожної колір (1), центр (2), кількість клітинок (1),
+// клітинки по байту (x у старших 4 бітах, y у молодших), довжина назви (1) та назва
+fn encode_pieces(pieces: &PieceSet, bytes: &mut Vec<u8>) {
+    let PieceSet::Custom(pieces) = pieces else {
+        return;
+    };
+    bytes.push(pieces.len() as u8);
+    for piece in pieces {
+        bytes.push(piece.color);
+        bytes.push(piece.rotation_center.0);
+        bytes.push(piece.rotation_center.1);
+        bytes.push(piece.cells.len() as u8);
+        bytes.extend(piece.cells.iter().map(|(x, y)| x << 4 | y & 0xF));
+        bytes.push(piece.name.len() as u8);
+        bytes.extend_from_slice(piece.name.as_bytes());
+    }
+}
+
+// Повертає власні фігури та кількість прочитаних байтів
+fn decode_pieces(bytes: &[u8]) -> Result<(Vec<PieceDefinition>, usize), CodecError> {
+    let mut position = 0;
+    let mut take = |len: usize| {
+        let slice = bytes
+            .get(position..position + len)
+            .ok_or(CodecError::Truncated)?;
+        position += len;
+        Ok(slice)
+    };
+
+    let count = take(1)?[0];
+    let mut pieces = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let [color, center_x, center_y, cell_count] = take(4)?.try_into().unwrap();
+        let cells = take(cell_count as usize)?
+            .iter()
+            .map(|cell| (cell >> 4, cell & 0xF))
+            .collect();
+        let name_len = take(1)?[0];
+        let name = String::from_utf8(take(name_len as usize)?.to_vec())
+            .map_err(|_| CodecError::InvalidRules)?;
+        pieces.push(PieceDefinition {
+            name,
+            color,
+            cells,
+            rotation_center: (center_x, center_y),
+        });
+    }
+    Ok((pieces, position))
+}
+
+// Повертає правила та загальну довжину їхнього запису
+fn decode_rules(bytes: &[u8], width: u8, height: u8) -> Result<(RuleSet, usize), CodecError> {
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    let flag_at = |offset: usize| match bytes[offset] {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(CodecError::InvalidRules),
+    };
+
+    let mut rules = RuleSet {
+        rotation: match bytes[0] {
+            0 => RotationSystem::Classic,
+            1 => RotationSystem::Srs,
+            2 => RotationSystem::Ars,
+            3 => RotationSystem::Nes,
+            _ => return Err(CodecError::InvalidRules),
+        },
+        randomizer: match bytes[1] {
+            0 => Randomizer::Random,
+            1 => Randomizer::SevenBag,
+            2 => Randomizer::Nes,
+            _ => return Err(CodecError::InvalidRules),
+        },
+        scoring: Scoring {
+            line_clear: [u32_at(2), u32_at(6), u32_at(10), u32_at(14)],
+            level_multiplier: flag_at(18)?,
+            perfect_clear: [u32_at(38), u32_at(42), u32_at(46), u32_at(50)],
+            back_to_back_perfect_clear: u32_at(54),
+        },
+        levels: match bytes[19] {
+            0 => LevelSystem::Guideline,
+            1 => LevelSystem::Nes,
+            _ => return Err(CodecError::InvalidRules),
+        },
+        start_level: bytes[20],
+        lock_delay_micros: u64::from_le_bytes(bytes[21..29].try_into().unwrap()),
+        max_lock_resets: u32_at(29),
+        hold: flag_at(33)?,
+        preview: bytes[34],
+        bomb_chance: u16::from_le_bytes([bytes[35], bytes[36]]),
+        width,
+        height,
+        pieces: PieceSet::Tetrominoes,
+        dig_rows: bytes[58],
+        garbage_interval_micros: u64::from_le_bytes(bytes[59..67].try_into().unwrap()),
+        clear_gravity: match bytes[67] {
+            0 => ClearGravity::Naive,
+            1 => ClearGravity::Cascade,
+            _ => return Err(CodecError::InvalidRules),
+        },
+        visibility: match bytes[68] {
+            0 => Visibility::Full,
+            1 => Visibility::Invisible {
+                after_pieces: bytes[69],
+            },
+            2 => Visibility::Fog { rows: bytes[69] },
+            _ => return Err(CodecError::InvalidRules),
+        },
+    };
+    let mut len = RULES_LEN;
+    rules.pieces = match bytes[37] {
+        0 => PieceSet::Tetrominoes,
+        1 => PieceSet::Triominoes,
+        2 => PieceSet::Pentominoes,
+        3 => {
+            let (pieces, pieces_len) = decode_pieces(&bytes[RULES_LEN..])?;
+            len += pieces_len;
+            PieceSet::Custom(pieces)
+        }
+        _ => return Err(CodecError::InvalidRules),
+    };
+    rules.validate().map_err(|_| CodecError::InvalidRules)?;
+    Ok((rules, len))
+}
+
+fn move_code(action: &GameAction) -> Option<u8> {
+    match action {
+        GameAction::MoveLeft => Some(OP_MOVE_LEFT),
+        GameAction::MoveRight => Some(OP_MOVE_RIGHT),
+        GameAction::Rotate => Some(OP_ROTATE),
+        _ => None,
+    }
+}
+
+fn move_from_code(code: u8) -> Option<GameAction> {
+    match code {
+        OP_MOVE_LEFT => Some(GameAction::MoveLeft),
+        OP_MOVE_RIGHT => Some(GameAction::MoveRight),
+        OP_ROTATE => Some(GameAction::Rotate),
+        _ => None,
+    }
+}
+
+// Кодує запис гри. Час записується окремою дією лише тоді, коли він змінюється,
+// тож дії в межах одного блоку не потребують додаткових бітів.
+pub fn encode(replay: &Replay) -> Result<Vec<u8>, CodecError> {
+    let start = replay
+        .entries
+        .first()
+        .map_or(0, |entry| entry.timestamp_micros);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.push(replay.version);
+    header.extend_from_slice(&replay.rules_version.to_le_bytes());
+    header.extend_from_slice(&replay.seed.to_le_bytes());
+    header.push(mode_code(replay.mode));
+    header.push(replay.rules.width);
+    header.push(replay.rules.height);
+    header.extend_from_slice(&start.to_le_bytes());
+    encode_rules(&replay.rules, &mut header);
+    encode_pieces(&replay.rules.pieces, &mut header);
+
+    let mut writer = NibbleWriter {
+        bytes: header,
+        high: false,
+    };
+    let mut last_timestamp = start;
+    let mut index = 0;
+
+    while index < replay.entries.len() {
+        let entry = &replay.entries[index];
+        if entry.timestamp_micros != last_timestamp {
+            writer.push(OP_TIME);
+            writer.push_varint(timestamp_delta(last_timestamp, entry)?);
+            last_timestamp = entry.timestamp_micros;
+        }
+
+        match &entry.input {
+            ReplayInput::Action(action) => {
+                if let Some(code) = move_code(action) {
+                    // Рахуємо однакові рухи поспіль у той самий момент
+                    let run = replay.entries[index..]
+                        .iter()
+                        .take_while(|other| {
+                            other.input == entry.input
+                                && other.timestamp_micros == entry.timestamp_micros
+                        })
+                        .count();
+                    writer.push(code);
+                    let mut remaining = run - 1;
+                    while remaining >= MIN_REPEAT {
+                        let count = remaining.min(MAX_REPEAT);
+                        writer.push(OP_REPEAT);
+                        writer.push((count - MIN_REPEAT) as u8);
+                        remaining -= count;
+                    }
+                    if remaining == 1 {
+                        writer.push(code);
+                    }
+                    index += run;
+                    continue;
+                }
+
+                writer.push(match action {
+                    GameAction::Drop => OP_DROP,
+                    GameAction::GameOver => OP_GAME_OVER,
+                    GameAction::Pause => OP_PAUSE,
+                    GameAction::Resume => OP_RESUME,
+                    GameAction::Hold => OP_HOLD,
+                    _ => return Err(CodecError::UnsupportedAction),
+                });
+            }
+            ReplayInput::Garbage(attack) => {
+                writer.push(OP_GARBAGE);
+                writer.push_varint(attack.lines as u64);
+                writer.push_varint(attack.hole_column as u64);
+            }
+        }
+        index += 1;
+    }
+
+    Ok(writer.finish())
+}
+
+fn timestamp_delta(last_timestamp: u64, entry: &ReplayEntry) -> Result<u64, CodecError> {
+    entry
+        .timestamp_micros
+        .checked_sub(last_timestamp)
+        .ok_or(CodecError::TimestampOrder)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Replay, CodecError> {
+    let version = *bytes.first().ok_or(CodecError::Truncated)?;
+    if version != crate::replay::REPLAY_VERSION {
+        return Err(CodecError::UnsupportedVersion(version));
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(CodecError::Truncated);
+    }
+
+    let rules_version = u16::from_le_bytes([bytes[1], bytes[2]]);
+    let seed = u64::from_le_bytes(bytes[3..11].try_into().unwrap());
+    let mode = mode_from_code(bytes[11])?;
+    let (width, height) = (bytes[12], bytes[13]);
+    let sizes = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+    if !sizes.contains(&width) || !sizes.contains(&height) {
+        return Err(CodecError::UnsupportedBoard { width, height });
+    }
+    let mut timestamp_micros = u64::from_le_bytes(bytes[14..22].try_into().unwrap());
+    let (rules, rules_len) = decode_rules(&bytes[22..], width, height)?;
+
+    let mut reader = NibbleReader {
+        bytes: &bytes[22 + rules_len..],
+        position: 0,
+    };
+    let mut entries: Vec<ReplayEntry> = Vec::new();
+
+    while let Some(code) = reader.next() {
+        let input = match code {
+            OP_MOVE_LEFT | OP_MOVE_RIGHT | OP_ROTATE => {
+                ReplayInput::Action(move_from_code(code).unwrap())
+            }
+            OP_REPEAT => {
+                let count = reader.next().ok_or(CodecError::Truncated)? as usize + MIN_REPEAT;
+                let previous = entries
+                    .last()
+                    .filter(|entry| matches!(&entry.input, ReplayInput::Action(action) if move_code(action).is_some()))
+                    .cloned()
+                    .ok_or(CodecError::UnknownOpcode(OP_REPEAT))?;
+                for _ in 0..count {
+                    entries.push(ReplayEntry {
+                        timestamp_micros,
+                        input: previous.input.clone(),
+                    });
+                }
+                continue;
+            }
+            OP_TIME => {
+                timestamp_micros = timestamp_micros
+                    .checked_add(reader.next_varint()?)
+                    .ok_or(CodecError::TimestampOverflow)?;
+                continue;
+            }
+            OP_DROP => ReplayInput::Action(GameAction::Drop),
+            OP_GAME_OVER => ReplayInput::Action(GameAction::GameOver),
+            OP_PAUSE => ReplayInput::Action(GameAction::Pause),
+            OP_RESUME => ReplayInput::Action(GameAction::Resume),
+            OP_HOLD => ReplayInput::Action(GameAction::Hold),
+            OP_GARBAGE => {
+                let lines = reader.next_varint()? as u32;
+                let hole_column = reader.next_varint()? as u8;
+                ReplayInput::Garbage(GarbageAttack { lines, hole_column })
+            }
+            OP_END => break,
+            _ => return Err(CodecError::UnknownOpcode(code)),
+        };
+        entries.push(ReplayEntry {
+            timestamp_micros,
+            input,
+        });
+    }
+
+    Ok(Replay {
+        version,
+        rules_version,
+        seed,
+        mode,
+        rules,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay;
+
+    fn sample_replay() -> Replay {
+        let mut replay = Replay::new(0x1234_5678_9abc_def0, GameMode::Versus, RuleSet::default());
+        let mut timestamp = 1_700_000_000_000_000;
+        for round in 0..6u64 {
+            for _ in 0..(round % 4) {
+                replay.record_action(timestamp, GameAction::Rotate);
+            }
+            for _ in 0..(round % 4) {
+                replay.record_action(timestamp, GameAction::MoveLeft);
+            }
+            replay.record_action(timestamp, GameAction::MoveRight);
+            timestamp += 350_000 + round * 1_000;
+            replay.record_action(timestamp, GameAction::Drop);
+            if round == 3 {
+                timestamp += 10;
+                replay.record_garbage(
+                    timestamp,
+                    GarbageAttack {
+                        lines: 2,
+                        hole_column: 7,
+                    },
+                );
+            }
+        }
+        replay.record_action(timestamp, GameAction::GameOver);
+        replay
+    }
+
+    #[test]
+    fn round_trip_preserves_replay() {
+        let replay = sample_replay();
+        let bytes = encode(&replay).unwrap();
+        assert_eq!(decode(&bytes).unwrap(), replay);
+    }
+
+    #[test]
+    fn round_trip_preserves_simulation() {
+        let replay = sample_replay();
+        let decoded = decode(&encode(&replay).unwrap()).unwrap();
+        let original = replay::simulate(&replay).unwrap();
+        let restored = replay::simulate(&decoded).unwrap();
+        assert_eq!(original.board, restored.board);
+        assert_eq!(original.score, restored.score);
+    }
+
+    #[test]
+    fn round_trip_empty_replay() {
+        let replay = Replay::new(42, GameMode::Marathon, RuleSet::default());
+        let bytes = encode(&replay).unwrap();
+        assert_eq!(bytes.len(), HEADER_LEN);
+        assert_eq!(decode(&bytes).unwrap(), replay);
+    }
+
+    #[test]
+    fn long_runs_are_compressed() {
+        let mut replay = Replay::new(7, GameMode::Marathon, RuleSet::default());
+        for _ in 0..40 {
+            replay.record_action(0, GameAction::MoveRight);
+        }
+        let bytes = encode(&replay).unwrap();
+        // 1 ніббл дії + 3 повтори по 2 ніббли
+        assert_eq!(bytes.len(), HEADER_LEN + 4);
+        assert_eq!(decode(&bytes).unwrap(), replay);
+    }
+
+    #[test]
+    fn moves_take_at_most_four_bits() {
+        let mut replay = Replay::new(7, GameMode::Marathon, RuleSet::default());
+        for index in 0..100 {
+            let action = if index % 2 == 0 {
+                GameAction::MoveLeft
+            } else {
+                GameAction::Rotate
+            };
+            replay.record_action(0, action);
+        }
+        let bytes = encode(&replay).unwrap();
+        assert_eq!(bytes.len(), HEADER_LEN + 50);
+    }
+
+    #[test]
+    fn header_is_validated() {
+        let mut bytes = encode(&sample_replay()).unwrap();
+        bytes[12] = 2;
+        assert_eq!(
+            decode(&bytes),
+            Err(CodecError::UnsupportedBoard {
+                width: 2,
+                height: 20
+            })
+        );
+
+        bytes[0] = 99;
+        assert_eq!(decode(&bytes), Err(CodecError::UnsupportedVersion(99)));
+        assert_eq!(decode(&bytes[..1]), Err(CodecError::UnsupportedVersion(99)));
+        assert_eq!(
+            decode(&encode(&sample_replay()).unwrap()[..10]),
+            Err(CodecError::Truncated)
+        );
+    }
+
+    #[test]
+    fn pauses_keep_their_timestamps() {
+        let mut replay = Replay::new(3, GameMode::Marathon, RuleSet::default());
+        replay.record_action(1_000, GameAction::MoveLeft);
+        replay.record_action(2_000, GameAction::Pause);
+        replay.record_action(90_000_000, GameAction::Resume);
+        replay.record_action(90_500_000, GameAction::Drop);
+
+        let decoded = decode(&encode(&replay).unwrap()).unwrap();
+        assert_eq!(decoded, replay);
+        assert_eq!(
+            replay::simulate(&decoded).unwrap().paused_micros,
+            89_998_000
+        );
+    }
+
+    #[test]
+    fn rules_are_stored_in_header() {
+        let rules = RuleSet {
+            bomb_chance: 25,
+            width: 12,
+            height: 24,
+            clear_gravity: ClearGravity::Cascade,
+            visibility: Visibility::Fog { rows: 4 },
+            ..RuleSet::guideline()
+        };
+        let mut replay = Replay::new(11, GameMode::Marathon, rules);
+        replay.record_action(0, GameAction::Hold);
+        replay.record_action(0, GameAction::Rotate);
+        replay.record_action(400_000, GameAction::Drop);
+        let bytes = encode(&replay).unwrap();
+        assert_eq!(decode(&bytes).unwrap(), replay);
+        assert_eq!(replay::simulate(&replay).unwrap().board[0].len(), 12);
+
+        let mut invalid = bytes.clone();
+        invalid[22] = 9;
+        assert_eq!(decode(&invalid), Err(CodecError::InvalidRules));
+    }
+
+    #[test]
+    fn rule_presets_round_trip() {
+        for rules in [RuleSet::default(), RuleSet::guideline(), RuleSet::nes()] {
+            let mut replay = Replay::new(13, GameMode::Marathon, rules);
+            replay.record_action(0, GameAction::Rotate);
+            replay.record_action(900_000, GameAction::Drop);
+            assert_eq!(decode(&encode(&replay).unwrap()).unwrap(), replay);
+        }
+    }
+
+    #[test]
+    fn piece_sets_round_trip() {
+        let custom = PieceSet::Custom(vec![PieceDefinition {
+            name: "Domino".to_string(),
+            color: 3,
+            cells: vec![(0, 0), (1, 0)],
+            rotation_center: (2, 2),
+        }]);
+        for pieces in [PieceSet::Triominoes, PieceSet::Pentominoes, custom] {
+            let rules = RuleSet {
+                pieces,
+                ..RuleSet::guideline()
+            };
+            let mut replay = Replay::new(17, GameMode::Marathon, rules);
+            replay.record_action(0, GameAction::Rotate);
+            replay.record_action(0, GameAction::Drop);
+            replay.record_action(300_000, GameAction::MoveLeft);
+            let bytes = encode(&replay).unwrap();
+            assert_eq!(decode(&bytes).unwrap(), replay);
+            assert!(replay::simulate(&replay).is_ok());
+        }
+
+        let disconnected = RuleSet {
+            pieces: PieceSet::Custom(vec![PieceDefinition {
+                name: "Gap".to_string(),
+                color: 1,
+                cells: vec![(0, 0), (2, 0)],
+                rotation_center: (3, 1),
+            }]),
+            ..RuleSet::default()
+        };
+        assert!(disconnected.validate().is_err());
+    }
+
+    #[test]
+    fn practice_boards_are_replayed() {
+        let replay = Replay::new(21, GameMode::PerfectClearPractice, RuleSet::default());
+        let decoded = decode(&encode(&replay).unwrap()).unwrap();
+        let state = replay::simulate(&decoded).unwrap();
+        let filled = state
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_some())
+            .count();
+        assert!(filled > 0 && filled % 4 == 0);
+        assert_eq!(state.board, replay::simulate(&replay).unwrap().board);
+    }
+
+    #[test]
+    fn non_replay_actions_are_rejected() {
+        let mut replay = Replay::new(7, GameMode::Marathon, RuleSet::default());
+        replay.record_action(0, GameAction::StartGame);
+        assert_eq!(encode(&replay), Err(CodecError::UnsupportedAction));
+    }
+
+    #[test]
+    fn overflowing_time_delta_is_rejected() {
+        let mut replay = Replay::new(7, GameMode::Marathon, RuleSet::default());
+        replay.record_action(0, GameAction::MoveLeft);
+        replay.record_action(u64::MAX, GameAction::Drop);
+        let mut bytes = encode(&replay).unwrap();
+        assert_eq!(decode(&bytes).unwrap(), replay);
+
+        // Час початку в заголовку (байти 14..22) зсунуто так, що приріст переповнює u64
+        bytes[14..22].copy_from_slice(&1u64.to_le_bytes());
+        assert_eq!(decode(&bytes), Err(CodecError::TimestampOverflow));
+    }
+}
@@ -0,0 +1,1369 @@
+use crate::{
+    cascade, garbage,
+    pieces::PieceSet,
+    practice, random, rotate_shape,
+    rules::{ClearGravity, LevelSystem, RotationSystem, RuleSet},
+    GameAction, GameMode, GameState, Piece, PieceType, Position, BOMB_SHAPE, TETROMINOES,
+};
+
+pub struct StepResult {
+    pub message: &'static str,
+    // Усі рядки, очищені за дію, та найбільше очищення однією фігурою
+    pub lines_cleared: u32,
+    pub largest_clear: u32,
+    // Кількість рядків сміття для суперника
+    pub attack: u32,
+    pub t_spin: bool,
+    pub perfect_clear: bool,
+}
+
+impl StepResult {
+    fn empty(message: &'static str) -> Self {
+        Self {
+            message,
+            lines_cleared: 0,
+            largest_clear: 0,
+            attack: 0,
+            t_spin: false,
+            perfect_clear: false,
+        }
+    }
+
+    // Поєднує фіксації від гравітації з результатом дії гравця
+    fn then(self, next: StepResult) -> StepResult {
+        StepResult {
+            message: next.message,
+            lines_cleared: self.lines_cleared + next.lines_cleared,
+            largest_clear: self.largest_clear.max(next.largest_clear),
+            attack: self.attack + next.attack,
+            t_spin: self.t_spin || next.t_spin,
+            perfect_clear: self.perfect_clear || next.perfect_clear,
+        }
+    }
+}
+
+// Час падіння на один рядок для рівнів 1-15 за Tetris Guideline; далі швидкість не зростає
+const GRAVITY_MICROS: [u64; 15] = [
+    1_000_000, 793_460, 617_800, 472_730, 355_200, 262_000, 189_680, 134_880, 94_180, 64_630,
+    43_520, 28_740, 18_610, 11_800, 7_350,
+];
+
+// Кадри NES на один рядок для рівнів 0-29; з 29 рівня (kill screen) фігура падає щокадру
+const NES_GRAVITY_FRAMES: [u64; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    1,
+];
+// Тривалість кадру NES (60.0988 Гц)
+const NES_FRAME_MICROS: u64 = 16_639;
+
+// Поштовхи SRS для повороту за годинниковою стрілкою з положень 0, R, 2, L
+// (вісь y спрямована вниз, тож знаки y протилежні до таблиць guideline)
+const SRS_KICKS: [[(i32, i32); 5]; 4] = [
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+];
+const SRS_I_KICKS: [[(i32, i32); 5]; 4] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+];
+
+// Застосовує ігрову дію до стану. Використовується і контрактом, і для перевірки
+// записів гри, тому результат залежить лише від стану, дії та часу блоку.
+pub fn apply_action(
+    state: &mut GameState,
+    action: &GameAction,
+    timestamp_micros: u64,
+) -> Result<StepResult, &'static str> {
+    if let GameAction::GameOver = action {
+        state.game_over = true;
+        state.current_piece = None;
+        state.paused_at = None;
+        return Ok(StepResult::empty("Game over"));
+    }
+
+    if state.game_over || state.current_piece.is_none() {
+        return Err("Game not in progress");
+    }
+
+    // Невдала дія не повинна змінювати стан, тож гравітацію застосовуємо до копії
+    let mut next = state.clone();
+    update_clock(&mut next, timestamp_micros);
+    let mut result = rise_garbage(&mut next);
+    if !next.game_over {
+        result = result.then(apply_gravity(&mut next, timestamp_micros));
+    }
+    if !next.game_over {
+        result = result.then(apply_input(&mut next, action, timestamp_micros)?);
+    }
+    // У виживанні рахунок - прожиті секунди
+    if next.mode == GameMode::Survival {
+        next.score = (next.elapsed_micros / 1_000_000) as u32;
+    }
+    *state = next;
+    Ok(result)
+}
+
+// Ігровий час рахується від першої дії; час на паузі не враховується
+fn update_clock(state: &mut GameState, timestamp_micros: u64) {
+    let started_at = *state.started_at.get_or_insert(timestamp_micros);
+    if state.paused_at.is_none() {
+        state.elapsed_micros = timestamp_micros
+            .saturating_sub(started_at)
+            .saturating_sub(state.paused_micros);
+    }
+}
+
+// У виживанні піднімає стільки рядків сміття, скільки належить за ігровим часом.
+// Фігура, в яку влучило сміття, піднімається на рядок; якщо й там їй немає місця,
+// гра завершується.
+fn rise_garbage(state: &mut GameState) -> StepResult {
+    if state.mode != GameMode::Survival {
+        return StepResult::empty("Garbage rose");
+    }
+    let due = (state.elapsed_micros / state.rules.garbage_interval_micros) as u32;
+    while state.garbage_risen < due {
+        if garbage::rise_one(state) {
+            return StepResult::empty("Game over");
+        }
+        let Some(mut piece) = state.current_piece.clone() else {
+            continue;
+        };
+        if !is_valid_move(state, &piece) {
+            piece.position.y -= 1;
+            if !is_valid_move(state, &piece) {
+                state.game_over = true;
+                state.current_piece = None;
+                return StepResult::empty("Game over");
+            }
+            state.current_piece = Some(piece);
+        }
+    }
+    StepResult::empty("Garbage rose")
+}
+
+fn apply_input(
+    state: &mut GameState,
+    action: &GameAction,
+    timestamp_micros: u64,
+) -> Result<StepResult, &'static str> {
+    match action {
+        GameAction::Pause => {
+            if is_multiplayer(state) {
+                return Err("Cannot pause a multiplayer game");
+            }
+            if state.mode.is_timed() {
+                return Err("Cannot pause a timed game");
+            }
+            if state.paused_at.is_some() {
+                return Err("Game is already paused");
+            }
+            state.paused_at = Some(timestamp_micros);
+            state.pauses_used += 1;
+            return Ok(StepResult::empty("Game paused"));
+        }
+        GameAction::Resume => {
+            let paused_at = state.paused_at.take().ok_or("Game is not paused")?;
+            state.paused_micros += timestamp_micros.saturating_sub(paused_at);
+            // Час на паузі не враховується гравітацією та затримкою фіксації
+            state.gravity_at = timestamp_micros;
+            if let Some(lock_started_at) = &mut state.lock_started_at {
+                *lock_started_at += timestamp_micros.saturating_sub(paused_at);
+            }
+            return Ok(StepResult::empty("Game resumed"));
+        }
+        _ if state.paused_at.is_some() => return Err("Game is paused"),
+        _ => {}
+    }
+
+    let mut updated_piece = state.current_piece.clone().unwrap();
+    let (message, error) = match action {
+        GameAction::MoveLeft => {
+            updated_piece.position.x -= 1;
+            ("Moved left", "Cannot move left")
+        }
+        GameAction::MoveRight => {
+            updated_piece.position.x += 1;
+            ("Moved right", "Cannot move right")
+        }
+        GameAction::Rotate => {
+            let from = updated_piece.rotation;
+            updated_piece.rotation =
+                (from + 1) % rotation_states(state.rules.rotation, updated_piece.piece_type);
+            // Перше положення з поштовхів системи повороту, де фігура вміщується
+            updated_piece = rotation_kicks(state.rules.rotation, updated_piece.piece_type, from)
+                .iter()
+                .map(|(dx, dy)| Piece {
+                    position: Position {
+                        x: updated_piece.position.x + dx,
+                        y: updated_piece.position.y + dy,
+                    },
+                    ..updated_piece.clone()
+                })
+                .find(|piece| is_valid_move(state, piece))
+                .ok_or("Cannot rotate")?;
+            ("Rotated", "Cannot rotate")
+        }
+        GameAction::Drop => return Ok(drop_piece(state, timestamp_micros)),
+        GameAction::Hold => return hold_piece(state, timestamp_micros),
+        _ => return Err("Not a game action"),
+    };
+
+    if !is_valid_move(state, &updated_piece) {
+        return Err(error);
+    }
+
+    state.current_piece = Some(updated_piece);
+    state.last_move_rotated = matches!(action, GameAction::Rotate);
+    update_lock_delay(state, timestamp_micros);
+    Ok(StepResult::empty(message))
+}
+
+fn rotation_states(system: RotationSystem, piece_type: PieceType) -> u8 {
+    match (system, piece_type) {
+        (RotationSystem::Nes, PieceType::I | PieceType::S | PieceType::Z) => 2,
+        _ => 4,
+    }
+}
+
+fn rotation_kicks(
+    system: RotationSystem,
+    piece_type: PieceType,
+    from: u8,
+) -> &'static [(i32, i32)] {
+    match (system, piece_type) {
+        (RotationSystem::Classic | RotationSystem::Nes, _)
+        | (_, PieceType::O)
+        | (RotationSystem::Ars, PieceType::I) => &[(0, 0)],
+        (RotationSystem::Ars, _) => &[(0, 0), (1, 0), (-1, 0)],
+        (RotationSystem::Srs, PieceType::I) => &SRS_I_KICKS[from as usize % 4],
+        (RotationSystem::Srs, _) => &SRS_KICKS[from as usize % 4],
+    }
+}
+
+// Відкладає поточну фігуру та бере попередньо відкладену (або наступну з черги)
+fn hold_piece(state: &mut GameState, timestamp_micros: u64) -> Result<StepResult, &'static str> {
+    if !state.rules.hold {
+        return Err("Hold is disabled");
+    }
+    if state.hold_used {
+        return Err("Piece was already held");
+    }
+
+    let current = state.current_piece.clone().unwrap().piece_type;
+    let piece = match state.held_piece.replace(current) {
+        Some(held) => Some(spawn_piece(state, held)),
+        None => Some(generate_new_piece(state).ok_or("No pieces left")?),
+    };
+    state.hold_used = true;
+    let message = if enter_piece(state, piece, timestamp_micros) {
+        "Piece held"
+    } else {
+        "Game over"
+    };
+    Ok(StepResult::empty(message))
+}
+
+// Рух фігури на опорі перезапускає затримку фіксації, а фігура, що зійшла з опори,
+// знову падає
+fn update_lock_delay(state: &mut GameState, timestamp_micros: u64) {
+    let grounded = state
+        .current_piece
+        .as_ref()
+        .is_some_and(|piece| fall_distance(state, piece) == 0);
+
+    match state.lock_started_at {
+        Some(_) if !grounded => {
+            state.lock_started_at = None;
+            state.lock_resets += 1;
+            state.gravity_at = timestamp_micros;
+        }
+        Some(_) if state.lock_resets < state.rules.max_lock_resets => {
+            state.lock_started_at = Some(timestamp_micros);
+            state.lock_resets += 1;
+        }
+        Some(_) => {}
+        None if grounded => touch_down(state, timestamp_micros),
+        None => {}
+    }
+}
+
+// Фігура торкнулася опори; після вичерпання скидань вона фіксується одразу
+fn touch_down(state: &mut GameState, timestamp_micros: u64) {
+    state.lock_started_at = Some(if state.lock_resets >= state.rules.max_lock_resets {
+        timestamp_micros.saturating_sub(state.rules.lock_delay_micros)
+    } else {
+        timestamp_micros
+    });
+}
+
+// Швидкість падіння на поточному рівні
+pub fn gravity_micros(rules: &RuleSet, level: u32) -> u64 {
+    match rules.levels {
+        LevelSystem::Guideline => {
+            GRAVITY_MICROS[(level.clamp(1, GRAVITY_MICROS.len() as u32) - 1) as usize]
+        }
+        LevelSystem::Nes => {
+            NES_GRAVITY_FRAMES[(level as usize).min(NES_GRAVITY_FRAMES.len() - 1)]
+                * NES_FRAME_MICROS
+        }
+    }
+}
+
+// Опускає фігуру на стільки рядків, скільки вона пролетіла б від попереднього кроку
+// гравітації, та фіксує фігури, затримка фіксації яких минула. Решта часу
+// припадає на наступні фігури.
+//
+// Поле, яке постійно очищується (наприклад, самими бомбами), може не заповнитися ніколи,
+// тож за одну дію гравітація фіксує не більше фігур, ніж клітинок на полі. Час понад
+// цю межу пропускається, і фігура, що лежить на опорі, знову чекає на фіксацію.
+fn apply_gravity(state: &mut GameState, timestamp_micros: u64) -> StepResult {
+    let mut result = StepResult::empty("Piece fell");
+    // Відлік починається з першої дії гри
+    if state.gravity_at == 0 {
+        state.gravity_at = timestamp_micros;
+        return result;
+    }
+
+    let max_locks = state.rules.width as u32 * state.rules.height as u32;
+    let mut locks = 0;
+    while !state.game_over && state.paused_at.is_none() {
+        let Some(mut piece) = state.current_piece.clone() else {
+            break;
+        };
+
+        if let Some(lock_started_at) = state.lock_started_at {
+            let locked_at = lock_started_at + state.rules.lock_delay_micros;
+            if timestamp_micros < locked_at {
+                break;
+            }
+            if locks == max_locks {
+                state.gravity_at = timestamp_micros;
+                state.lock_started_at = Some(timestamp_micros);
+                break;
+            }
+            locks += 1;
+            result = result.then(drop_piece(state, locked_at));
+            continue;
+        }
+
+        let row_micros = gravity_micros(&state.rules, level(&state.rules, state.lines));
+        let fall = fall_distance(state, &piece);
+        let rows = (timestamp_micros.saturating_sub(state.gravity_at) / row_micros).min(fall);
+        if rows > 0 {
+            piece.position.y += rows as i32;
+            state.current_piece = Some(piece);
+            state.gravity_at += rows * row_micros;
+            state.last_move_rotated = false;
+        }
+        if rows < fall {
+            break;
+        }
+        touch_down(state, state.gravity_at);
+    }
+
+    result
+}
+
+fn fall_distance(state: &GameState, piece: &Piece) -> u64 {
+    let mut below = piece.clone();
+    let mut rows = 0;
+    loop {
+        below.position.y += 1;
+        if !is_valid_move(state, &below) {
+            return rows;
+        }
+        rows += 1;
+    }
+}
+
+fn drop_piece(state: &mut GameState, timestamp_micros: u64) -> StepResult {
+    let mut current_piece = state.current_piece.clone().unwrap();
+    let spawn_y = current_piece.position.y;
+
+    // Опускаємо фігуру вниз, поки це можливо
+    while is_valid_move(
+        state,
+        &Piece {
+            position: Position {
+                x: current_piece.position.x,
+                y: current_piece.position.y + 1,
+            },
+            ..current_piece.clone()
+        },
+    ) {
+        current_piece.position.y += 1;
+    }
+
+    // T-спін: останнім рухом був поворот, фігура не падала,
+    // а щонайменше три кути навколо центру T зайняті
+    let t_spin = state.last_move_rotated
+        && current_piece.position.y == spawn_y
+        && is_t_spin(state, &current_piece);
+    state.last_move_rotated = false;
+
+    // Розміщуємо фігуру на дошці; бомба натомість вибухає
+    if current_piece.piece_type == PieceType::Bomb {
+        explode(state, &current_piece.position);
+    } else {
+        place_piece(state, &current_piece);
+    }
+
+    // Перевіряємо та очищаємо заповнені рядки
+    let lines_cleared = clear_lines(state);
+
+    // Perfect clear: після очищення на полі не лишилося жодної клітинки
+    let perfect_clear = lines_cleared > 0 && state.board.iter().flatten().all(Option::is_none);
+    if perfect_clear {
+        state.perfect_clears += 1;
+        state.score += state.rules.perfect_clear_points(
+            lines_cleared,
+            state.back_to_back,
+            level(&state.rules, state.lines),
+        );
+        if state.mode == GameMode::PerfectClearPractice {
+            practice::setup_board(state);
+        }
+    }
+    if lines_cleared > 0 {
+        state.back_to_back = lines_cleared >= 4 || t_spin;
+    }
+
+    // У режимах versus та королівської битви атакуємо суперника
+    // та отримуємо сміття з черги
+    let mut attack = 0;
+    let mut topped_out = false;
+    if is_multiplayer(state) {
+        attack = garbage::cancel_garbage(state, garbage::garbage_for_lines(lines_cleared));
+        topped_out = garbage::apply_incoming_garbage(state, 0, timestamp_micros);
+    }
+
+    // Перегони розкопок завершуються, щойно з поля зникло все сміття
+    let dig_finished = state.mode == GameMode::DigRace && garbage::garbage_remaining(state) == 0;
+
+    // Створюємо нову фігуру та перевіряємо, чи можна її розмістити
+    state.hold_used = false;
+    let message = if topped_out {
+        state.game_over = true;
+        state.current_piece = None;
+        "Game over"
+    } else if dig_finished {
+        state.game_over = true;
+        state.current_piece = None;
+        "Dig race finished"
+    } else {
+        let new_piece = generate_new_piece(state);
+        if enter_piece(state, new_piece, timestamp_micros) {
+            "Piece dropped"
+        } else {
+            "Game over"
+        }
+    };
+
+    StepResult {
+        message,
+        lines_cleared,
+        largest_clear: lines_cleared,
+        attack,
+        t_spin,
+        perfect_clear,
+    }
+}
+
+// Ставить нову фігуру на поле; якщо для неї немає місця або фігури скінчилися,
+// гра завершується
+fn enter_piece(state: &mut GameState, piece: Option<Piece>, timestamp_micros: u64) -> bool {
+    state.gravity_at = timestamp_micros;
+    state.lock_started_at = None;
+    state.lock_resets = 0;
+    state.last_move_rotated = false;
+    match piece {
+        Some(piece) if is_valid_move(state, &piece) => {
+            state.current_piece = Some(piece);
+            true
+        }
+        _ => {
+            state.game_over = true;
+            state.current_piece = None;
+            false
+        }
+    }
+}
+
+// Бомба очищає квадрат 3x3 з центром у своїй клітинці
+fn explode(state: &mut GameState, center: &Position) {
+    for y in center.y - 1..=center.y + 1 {
+        for x in center.x - 1..=center.x + 1 {
+            if let Some(cell) = state
+                .board
+                .get_mut(y as usize)
+                .and_then(|row| row.get_mut(x as usize))
+            {
+                *cell = None;
+            }
+        }
+    }
+}
+
+fn is_t_spin(state: &GameState, piece: &Piece) -> bool {
+    if piece.piece_type != PieceType::T {
+        return false;
+    }
+
+    // Центр T завжди в клітинці (1, 1) її рамки 3x3
+    let occupied_corners = [(0, 0), (2, 0), (0, 2), (2, 2)]
+        .iter()
+        .filter(|(dx, dy)| {
+            let x = piece.position.x + dx;
+            let y = piece.position.y + dy;
+            !(0..state.rules.width as i32).contains(&x)
+                || !(0..state.rules.height as i32).contains(&y)
+                || state.board[y as usize][x as usize].is_some()
+        })
+        .count();
+    occupied_corners >= 3
+}
+
+// Рівень за кількістю очищених ліній
+pub fn level(rules: &RuleSet, lines: u32) -> u32 {
+    let start = rules.start_level as u32;
+    match rules.levels {
+        // Новий рівень кожні 10 ліній
+        LevelSystem::Guideline => start.max(1) + lines / 10,
+        // Як у NES: з високого стартового рівня перший перехід настає пізніше
+        LevelSystem::Nes => {
+            let first = (start * 10 + 10).min((start * 10).saturating_sub(50).max(100));
+            if lines < first {
+                start
+            } else {
+                start + 1 + (lines - first) / 10
+            }
+        }
+    }
+}
+
+// Підготовлене поле на старті режиму
+pub fn prepare_board(state: &mut GameState) {
+    match state.mode {
+        GameMode::PerfectClearPractice => practice::setup_board(state),
+        GameMode::DigRace => garbage::setup_dig(state),
+        _ => {}
+    }
+}
+
+pub fn is_multiplayer(state: &GameState) -> bool {
+    state.mode.is_multiplayer()
+}
+
+pub fn generate_new_piece(state: &mut GameState) -> Option<Piece> {
+    // Наступна фігура визначається зерном гри та її номером у послідовності,
+    // тож суперники з однаковим зерном отримують однакові фігури. Черга тримає
+    // ще стільки фігур наперед, скільки показує попередній перегляд.
+    while state.next_pieces.len() <= state.rules.preview as usize {
+        let index = state.piece_count + state.next_pieces.len() as u32;
+        // Головоломка має фіксовану послідовність фігур; коли вона скінчилася,
+        // нових фігур немає
+        if !state.piece_sequence.is_empty() {
+            match state.piece_sequence.get(index as usize) {
+                Some(piece_type) => state.next_pieces.push(*piece_type),
+                None => break,
+            }
+            continue;
+        }
+        // Попередня згенерована фігура: остання в черзі або поточна
+        let previous = state
+            .next_pieces
+            .last()
+            .copied()
+            .or(state.current_piece.as_ref().map(|piece| piece.piece_type));
+        let piece_type = if random::is_bomb(state.seed, index, state.rules.bomb_chance) {
+            PieceType::Bomb
+        } else {
+            random::piece_at(
+                state.rules.randomizer,
+                &state.rules.pieces,
+                state.seed,
+                index,
+                previous,
+            )
+        };
+        state.next_pieces.push(piece_type);
+    }
+
+    if state.next_pieces.is_empty() {
+        return None;
+    }
+    let piece_type = state.next_pieces.remove(0);
+    state.piece_count += 1;
+    Some(spawn_piece(state, piece_type))
+}
+
+fn spawn_piece(state: &GameState, piece_type: PieceType) -> Piece {
+    // Широкі фігури зсуваються вліво, щоб уміститися на вузькому полі
+    let right = piece_cells(&state.rules.pieces, piece_type, 0)
+        .iter()
+        .map(|(x, _)| x + 1)
+        .max()
+        .unwrap_or(1);
+    Piece {
+        piece_type,
+        position: Position {
+            x: (state.rules.width as i32 / 2 - 1).min(state.rules.width as i32 - right),
+            y: 0,
+        },
+        rotation: 0,
+    }
+}
+
+// Клітинки фігури відносно її позиції з урахуванням повороту
+pub fn piece_cells(pieces: &PieceSet, piece_type: PieceType, rotation: u8) -> Vec<(i32, i32)> {
+    let PieceType::Custom(index) = piece_type else {
+        let shape = if piece_type == PieceType::Bomb {
+            BOMB_SHAPE
+        } else {
+            TETROMINOES
+                .iter()
+                .find(|(pt, _)| *pt == piece_type)
+                .unwrap()
+                .1
+        };
+        return rotate_shape(shape, rotation)
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, cell)| **cell)
+                    .map(move |(x, _)| (x as i32, y as i32))
+            })
+            .collect();
+    };
+
+    let Some((cells, (center_x, center_y))) = pieces.shape(index as usize) else {
+        return Vec::new();
+    };
+    // Повертаємо за годинниковою стрілкою навколо центру в координатах
+    // половин клітинки, де середина клітинки (x, y) - це (2x + 1, 2y + 1)
+    let (center_x, center_y) = (center_x as i32, center_y as i32);
+    cells
+        .iter()
+        .map(|&(x, y)| {
+            let (mut x, mut y) = (2 * x as i32 + 1, 2 * y as i32 + 1);
+            for _ in 0..rotation % 4 {
+                (x, y) = (center_x + center_y - y, center_y - center_x + x);
+            }
+            ((x - 1).div_euclid(2), (y - 1).div_euclid(2))
+        })
+        .collect()
+}
+
+pub fn is_valid_move_with_board(
+    board: &[Vec<Option<PieceType>>],
+    pieces: &PieceSet,
+    piece: &Piece,
+) -> bool {
+    let height = board.len() as i32;
+    let width = board.first().map_or(0, |row| row.len()) as i32;
+    let cells = piece_cells(pieces, piece.piece_type, piece.rotation);
+
+    // Фігура без клітинок (невідомий номер у наборі) не може бути на полі
+    !cells.is_empty()
+        && cells.iter().all(|(x, y)| {
+            let board_x = piece.position.x + x;
+            let board_y = piece.position.y + y;
+
+            // Перевіряємо межі дошки та колізії
+            (0..width).contains(&board_x)
+                && (0..height).contains(&board_y)
+                && board[board_y as usize][board_x as usize].is_none()
+        })
+}
+
+pub fn is_valid_move(state: &GameState, piece: &Piece) -> bool {
+    is_valid_move_with_board(&state.board, &state.rules.pieces, piece)
+}
+
+pub fn place_piece(state: &mut GameState, piece: &Piece) {
+    for (x, y) in piece_cells(&state.rules.pieces, piece.piece_type, piece.rotation) {
+        let board_x = piece.position.x + x;
+        let board_y = piece.position.y + y;
+        state.board[board_y as usize][board_x as usize] = Some(piece.piece_type);
+        if let Some(stamps) = &mut state.lock_stamps {
+            stamps[board_y as usize][board_x as usize] = state.piece_count;
+        }
+    }
+}
+
+pub fn clear_lines(state: &mut GameState) -> u32 {
+    let level = level(&state.rules, state.lines);
+    let mut lines_cleared = remove_full_rows(state);
+    let mut points = state.rules.line_clear_points(lines_cleared, level);
+
+    // Каскад: після очищення групи клітинок падають і можуть заповнити нові рядки
+    if state.rules.clear_gravity == ClearGravity::Cascade && lines_cleared > 0 {
+        let mut chain = 1;
+        loop {
+            cascade::settle(state);
+            let lines = remove_full_rows(state);
+            if lines == 0 {
+                break;
+            }
+            chain += 1;
+            points += state.rules.line_clear_points(lines, level) * chain;
+            lines_cleared += lines;
+        }
+    }
+
+    // Оновлюємо рахунок за таблицею правил
+    state.score += points;
+    state.lines += lines_cleared;
+    lines_cleared
+}
+
+fn remove_full_rows(state: &mut GameState) -> u32 {
+    let mut lines_cleared = 0;
+    let mut y = state.board.len() - 1;
+
+    while y > 0 {
+        if state.board[y].iter().all(|cell| cell.is_some()) {
+            if state.board[y].contains(&Some(PieceType::Garbage)) {
+                state.garbage_cleared += 1;
+            }
+            // Зсуваємо всі рядки вище вниз і додаємо порожній зверху
+            state.board.remove(y);
+            state
+                .board
+                .insert(0, vec![None; state.rules.width as usize]);
+            if let Some(stamps) = &mut state.lock_stamps {
+                stamps.remove(y);
+                stamps.insert(0, vec![0; state.rules.width as usize]);
+            }
+            lines_cleared += 1;
+            // Не змінюємо y, щоб перевірити той самий рядок знову
+        } else {
+            y -= 1;
+        }
+    }
+    lines_cleared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pieces::PieceDefinition, rules::Randomizer};
+
+    fn step(lines_cleared: u32, attack: u32) -> StepResult {
+        StepResult {
+            lines_cleared,
+            largest_clear: lines_cleared,
+            attack,
+            ..StepResult::empty("Piece dropped")
+        }
+    }
+
+    #[test]
+    fn combined_steps_add_up() {
+        // Гравітація зафіксувала фігуру з двома рядками, потім гравець скинув ще одну
+        let result = step(2, 1).then(step(3, 2));
+        assert_eq!(result.lines_cleared, 5);
+        assert_eq!(result.largest_clear, 3);
+        assert_eq!(result.attack, 3);
+
+        let result = step(4, 4).then(StepResult::empty("Moved"));
+        assert_eq!(result.lines_cleared, 4);
+        assert_eq!(result.largest_clear, 4);
+        assert_eq!(result.message, "Moved");
+    }
+
+    fn started_state() -> GameState {
+        let mut state = GameState::new(RuleSet::default());
+        state.current_piece = generate_new_piece(&mut state);
+        // Перша дія запускає відлік гравітації
+        apply_action(&mut state, &GameAction::Rotate, 1_000).unwrap();
+        state
+    }
+
+    #[test]
+    fn gravity_follows_block_time() {
+        let mut state = started_state();
+        let row_micros = gravity_micros(&state.rules, level(&state.rules, 0));
+        let spawn_y = state.current_piece.as_ref().unwrap().position.y;
+
+        // Неповний рядок часу переноситься на наступну дію
+        let timestamp = 1_000 + 3 * row_micros + row_micros / 2;
+        apply_action(&mut state, &GameAction::MoveLeft, timestamp).unwrap();
+        assert_eq!(
+            state.current_piece.as_ref().unwrap().position.y,
+            spawn_y + 3
+        );
+        assert_eq!(state.gravity_at, 1_000 + 3 * row_micros);
+
+        apply_action(&mut state, &GameAction::MoveRight, 1_000 + 4 * row_micros).unwrap();
+        assert_eq!(
+            state.current_piece.as_ref().unwrap().position.y,
+            spawn_y + 4
+        );
+    }
+
+    #[test]
+    fn landed_piece_locks_after_delay() {
+        let mut state = started_state();
+        let row_micros = gravity_micros(&state.rules, level(&state.rules, 0));
+        let fall = fall_distance(&state, state.current_piece.as_ref().unwrap());
+        let landed_at = 1_000 + fall * row_micros;
+        let delay = state.rules.lock_delay_micros;
+
+        // Фігура лежить на опорі, але ще не зафіксована; поворот відкладає фіксацію
+        apply_action(&mut state, &GameAction::Rotate, landed_at + delay - 1).unwrap();
+        assert_eq!(state.piece_count, 1);
+        assert_eq!(state.lock_started_at, Some(landed_at + delay - 1));
+
+        // Фігура фіксується в момент, коли минула затримка, а не під час дії
+        let mut locked = started_state();
+        apply_action(&mut locked, &GameAction::MoveLeft, landed_at + delay + 1).unwrap();
+        assert_eq!(locked.piece_count, 2);
+        assert!(locked.board.iter().flatten().any(Option::is_some));
+        assert_eq!(locked.lock_started_at, None);
+    }
+
+    #[test]
+    fn gravity_locks_are_bounded() {
+        // Самі бомби очищують поле, тож гра не закінчується, скільки б часу не минуло
+        let mut state = GameState::new(RuleSet {
+            bomb_chance: 1000,
+            ..RuleSet::default()
+        });
+        state.current_piece = generate_new_piece(&mut state);
+        apply_action(&mut state, &GameAction::MoveLeft, 1).unwrap();
+
+        let max_locks = state.rules.width as u32 * state.rules.height as u32;
+        apply_action(&mut state, &GameAction::MoveRight, u64::MAX / 2).unwrap();
+        assert!(!state.game_over);
+        assert_eq!(state.piece_count, 1 + max_locks);
+        assert_eq!(state.gravity_at, u64::MAX / 2);
+
+        // Наступна дія одразу після межі вже не фіксує нових фігур
+        apply_action(&mut state, &GameAction::MoveLeft, u64::MAX / 2 + 1).unwrap();
+        assert_eq!(state.piece_count, 1 + max_locks);
+    }
+
+    // Фігура лежить на дні порожнього поля й щойно торкнулася опори
+    fn grounded_state(max_lock_resets: u32) -> GameState {
+        let mut state = GameState::new(RuleSet {
+            max_lock_resets,
+            ..RuleSet::default()
+        });
+        state.current_piece = generate_new_piece(&mut state);
+        state.gravity_at = 1;
+        let mut piece = state.current_piece.clone().unwrap();
+        piece.position.y += fall_distance(&state, &piece) as i32;
+        state.current_piece = Some(piece);
+        touch_down(&mut state, 1_000);
+        state
+    }
+
+    #[test]
+    fn lock_resets_are_capped() {
+        let mut state = grounded_state(2);
+        let delay = state.rules.lock_delay_micros;
+
+        apply_action(&mut state, &GameAction::MoveLeft, 2_000).unwrap();
+        apply_action(&mut state, &GameAction::MoveRight, 3_000).unwrap();
+        assert_eq!(state.lock_started_at, Some(3_000));
+        assert_eq!(state.lock_resets, 2);
+
+        // Понад ліміт рух уже не відкладає фіксацію
+        apply_action(&mut state, &GameAction::MoveLeft, 4_000).unwrap();
+        assert_eq!(state.lock_started_at, Some(3_000));
+        apply_action(&mut state, &GameAction::MoveRight, 3_000 + delay - 1).unwrap();
+        assert_eq!(state.piece_count, 1);
+        apply_action(&mut state, &GameAction::MoveLeft, 3_000 + delay).unwrap();
+        assert_eq!(state.piece_count, 2);
+    }
+
+    #[test]
+    fn exhausted_resets_lock_on_touch_down() {
+        let mut state = grounded_state(15);
+        let delay = state.rules.lock_delay_micros;
+
+        // Фігура, що зійшла з опори, знову падає, а скидання зараховується
+        let mut piece = state.current_piece.clone().unwrap();
+        piece.position.y -= 1;
+        state.current_piece = Some(piece);
+        update_lock_delay(&mut state, 2_000);
+        assert_eq!(state.lock_started_at, None);
+        assert_eq!(state.lock_resets, 1);
+        assert_eq!(state.gravity_at, 2_000);
+
+        // Після всіх скидань фігура фіксується, щойно торкнеться опори
+        state.lock_resets = state.rules.max_lock_resets;
+        touch_down(&mut state, delay + 5_000);
+        assert_eq!(state.lock_started_at, Some(5_000));
+        apply_action(&mut state, &GameAction::MoveLeft, delay + 5_000).unwrap();
+        assert_eq!(state.piece_count, 2);
+        assert_eq!(state.lock_resets, 0);
+    }
+
+    #[test]
+    fn paused_time_is_not_played() {
+        let mut state = GameState::new(RuleSet::default());
+        state.current_piece = generate_new_piece(&mut state);
+        apply_action(&mut state, &GameAction::MoveLeft, 1_000_000).unwrap();
+        apply_action(&mut state, &GameAction::Pause, 2_000_000).unwrap();
+
+        // На паузі фігура не рухається й не падає
+        let position = |state: &GameState| {
+            let piece = state.current_piece.as_ref().unwrap();
+            (piece.position.x, piece.position.y)
+        };
+        let paused_at = position(&state);
+        assert_eq!(
+            apply_action(&mut state, &GameAction::MoveRight, 3_000_000).err(),
+            Some("Game is paused")
+        );
+        assert!(apply_action(&mut state, &GameAction::Pause, 3_000_000).is_err());
+        apply_action(&mut state, &GameAction::Resume, 62_000_000).unwrap();
+        assert_eq!(position(&state), paused_at);
+        assert_eq!(state.paused_micros, 60_000_000);
+        assert_eq!(state.gravity_at, 62_000_000);
+
+        apply_action(&mut state, &GameAction::MoveRight, 62_500_000).unwrap();
+        assert_eq!(state.elapsed_micros, 1_500_000);
+        assert_eq!(state.pauses_used, 1);
+    }
+
+    #[test]
+    fn timed_and_multiplayer_games_cannot_pause() {
+        for mode in [GameMode::Survival, GameMode::Versus] {
+            let mut state = GameState {
+                mode,
+                ..GameState::new(RuleSet::default())
+            };
+            state.current_piece = generate_new_piece(&mut state);
+            assert!(apply_action(&mut state, &GameAction::Pause, 1).is_err());
+            assert_eq!(state.paused_at, None);
+        }
+    }
+
+    #[test]
+    fn srs_kicks_off_the_wall() {
+        // Вертикальна I біля лівої стіни: поворот на місці вийшов би за поле
+        let at_wall = |rotation| {
+            let mut state = GameState::new(RuleSet {
+                rotation,
+                ..RuleSet::default()
+            });
+            state.current_piece = Some(Piece {
+                piece_type: PieceType::I,
+                position: Position { x: -2, y: 5 },
+                rotation: 1,
+            });
+            state
+        };
+
+        let mut classic = at_wall(RotationSystem::Classic);
+        assert!(is_valid_move(
+            &classic,
+            classic.current_piece.as_ref().unwrap()
+        ));
+        assert_eq!(
+            apply_action(&mut classic, &GameAction::Rotate, 0).err(),
+            Some("Cannot rotate")
+        );
+
+        // Перший поштовх (-1, 0) теж за полем, тож спрацьовує другий (2, 0)
+        let mut srs = at_wall(RotationSystem::Srs);
+        apply_action(&mut srs, &GameAction::Rotate, 0).unwrap();
+        let piece = srs.current_piece.unwrap();
+        assert_eq!(
+            (piece.rotation, piece.position.x, piece.position.y),
+            (2, 0, 5)
+        );
+    }
+
+    #[test]
+    fn seven_bag_deals_every_piece() {
+        let mut state = GameState {
+            seed: 99,
+            ..GameState::new(RuleSet {
+                randomizer: Randomizer::SevenBag,
+                ..RuleSet::default()
+            })
+        };
+        let dealt: Vec<PieceType> = (0..21)
+            .map(|_| generate_new_piece(&mut state).unwrap().piece_type)
+            .collect();
+        for bag in dealt.chunks(7) {
+            for (piece_type, _) in TETROMINOES {
+                assert!(bag.contains(piece_type), "{bag:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn hold_swaps_once_per_piece() {
+        let mut state = GameState::new(RuleSet::guideline());
+        state.current_piece = generate_new_piece(&mut state);
+        let first = state.current_piece.as_ref().unwrap().piece_type;
+        let second = state.next_pieces[0];
+
+        apply_action(&mut state, &GameAction::Hold, 1_000).unwrap();
+        assert_eq!(state.held_piece, Some(first));
+        assert_eq!(state.current_piece.as_ref().unwrap().piece_type, second);
+        assert_eq!(
+            apply_action(&mut state, &GameAction::Hold, 2_000).err(),
+            Some("Piece was already held")
+        );
+
+        // Після фіксації відкладена фігура повертається з початкового положення
+        apply_action(&mut state, &GameAction::Drop, 3_000).unwrap();
+        apply_action(&mut state, &GameAction::Hold, 4_000).unwrap();
+        let piece = state.current_piece.as_ref().unwrap();
+        assert_eq!(
+            (piece.piece_type, piece.rotation, piece.position.y),
+            (first, 0, 0)
+        );
+
+        let mut classic = started_state();
+        assert_eq!(
+            apply_action(&mut classic, &GameAction::Hold, 2_000).err(),
+            Some("Hold is disabled")
+        );
+    }
+
+    #[test]
+    fn preview_shows_upcoming_pieces() {
+        let deal = |preview| {
+            let mut state = GameState {
+                seed: 5,
+                ..GameState::new(RuleSet {
+                    preview,
+                    ..RuleSet::guideline()
+                })
+            };
+            state.current_piece = generate_new_piece(&mut state);
+            state
+        };
+
+        let mut state = deal(5);
+        let upcoming = state.next_pieces.clone();
+        assert_eq!(upcoming.len(), 5);
+        for (timestamp, expected) in (1..).zip(upcoming) {
+            apply_action(&mut state, &GameAction::Drop, timestamp).unwrap();
+            assert_eq!(state.current_piece.as_ref().unwrap().piece_type, expected);
+            assert_eq!(state.next_pieces.len(), 5);
+        }
+
+        // Попередній перегляд лише показує фігури й не змінює їх порядок
+        let mut hidden = deal(0);
+        assert!(hidden.next_pieces.is_empty());
+        let dealt: Vec<PieceType> = (0..5)
+            .map(|_| generate_new_piece(&mut hidden).unwrap().piece_type)
+            .collect();
+        assert_eq!(dealt, deal(5).next_pieces);
+    }
+
+    #[test]
+    fn nes_scoring_counts_levels_from_zero() {
+        let scored = |rules: RuleSet, rows: usize| {
+            let mut state = GameState::new(rules);
+            let height = state.board.len();
+            for row in &mut state.board[height - rows..] {
+                row.fill(Some(PieceType::Garbage));
+            }
+            clear_lines(&mut state);
+            state.score
+        };
+
+        let nes = RuleSet::nes();
+        assert_eq!(scored(nes.clone(), 1), 40);
+        assert_eq!(scored(nes.clone(), 4), 1200);
+        let level_nine = RuleSet {
+            start_level: 9,
+            ..nes
+        };
+        assert_eq!(scored(level_nine.clone(), 1), 400);
+        assert_eq!(scored(level_nine, 4), 12_000);
+        assert_eq!(scored(RuleSet::guideline(), 4), 800);
+    }
+
+    #[test]
+    fn nes_first_level_transition() {
+        let from = |start_level| RuleSet {
+            start_level,
+            ..RuleSet::nes()
+        };
+        assert_eq!(level(&from(0), 9), 0);
+        assert_eq!(level(&from(0), 10), 1);
+        assert_eq!(level(&from(0), 25), 2);
+        // З 9 рівня перший перехід лише після 100 ліній
+        assert_eq!(level(&from(9), 99), 9);
+        assert_eq!(level(&from(9), 100), 10);
+        assert_eq!(level(&from(15), 99), 15);
+        assert_eq!(level(&from(15), 100), 16);
+        // З 18 рівня - після 130 ліній, далі кожні 10
+        assert_eq!(level(&from(18), 129), 18);
+        assert_eq!(level(&from(18), 130), 19);
+        assert_eq!(level(&from(18), 140), 20);
+        assert_eq!(level(&RuleSet::default(), 10), 2);
+    }
+
+    #[test]
+    fn nes_gravity_reaches_kill_screen() {
+        let nes = RuleSet::nes();
+        assert_eq!(gravity_micros(&nes, 0), 48 * NES_FRAME_MICROS);
+        assert_eq!(gravity_micros(&nes, 9), 6 * NES_FRAME_MICROS);
+        assert_eq!(gravity_micros(&nes, 19), 2 * NES_FRAME_MICROS);
+        assert_eq!(gravity_micros(&nes, 28), 2 * NES_FRAME_MICROS);
+        assert_eq!(gravity_micros(&nes, 29), NES_FRAME_MICROS);
+        assert_eq!(gravity_micros(&nes, 200), NES_FRAME_MICROS);
+    }
+
+    #[test]
+    fn nes_randomizer_rerolls_repeats() {
+        let mut state = GameState {
+            seed: 42,
+            ..GameState::new(RuleSet::nes())
+        };
+        let dealt: Vec<PieceType> = (0..2800)
+            .map(|_| generate_new_piece(&mut state).unwrap().piece_type)
+            .collect();
+        // Без перекидання повтор випадав би в кожній сьомій парі (400 разів),
+        // з ним - приблизно в кожній двадцять восьмій (100 разів)
+        let repeats = dealt.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        assert!((50..200).contains(&repeats), "{repeats}");
+    }
+
+    #[test]
+    fn nes_rotation_has_two_states_for_i_s_z() {
+        let rotations = |piece_type| {
+            let mut state = GameState::new(RuleSet::nes());
+            state.current_piece = Some(Piece {
+                piece_type,
+                position: Position { x: 3, y: 5 },
+                rotation: 0,
+            });
+            (0..4)
+                .map(|_| {
+                    apply_action(&mut state, &GameAction::Rotate, 0).unwrap();
+                    state.current_piece.as_ref().unwrap().rotation
+                })
+                .collect::<Vec<_>>()
+        };
+        for piece_type in [PieceType::I, PieceType::S, PieceType::Z] {
+            assert_eq!(rotations(piece_type), [1, 0, 1, 0]);
+        }
+        assert_eq!(rotations(PieceType::T), [1, 2, 3, 0]);
+    }
+
+    fn sorted_cells(pieces: &PieceSet, piece_type: PieceType, rotation: u8) -> Vec<(i32, i32)> {
+        let mut cells = piece_cells(pieces, piece_type, rotation);
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn pentominoes_turn_around_their_center() {
+        let pentominoes = PieceSet::Pentominoes;
+        // I5 повертається навколо середньої клітинки
+        let i5 = PieceType::Custom(2);
+        assert_eq!(
+            sorted_cells(&pentominoes, i5, 0),
+            [(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            sorted_cells(&pentominoes, i5, 1),
+            [(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]
+        );
+        // X симетричний, а чотири повороти повертають будь-яку фігуру на місце
+        let x = PieceType::Custom(13);
+        assert_eq!(
+            sorted_cells(&pentominoes, x, 1),
+            sorted_cells(&pentominoes, x, 0)
+        );
+        for index in 0..pentominoes.len() as u8 {
+            let piece_type = PieceType::Custom(index);
+            assert_eq!(
+                sorted_cells(&pentominoes, piece_type, 4),
+                sorted_cells(&pentominoes, piece_type, 0)
+            );
+            assert_eq!(piece_cells(&pentominoes, piece_type, 1).len(), 5);
+        }
+    }
+
+    #[test]
+    fn custom_pieces_turn_around_a_corner() {
+        let domino = PieceSet::Custom(vec![PieceDefinition {
+            name: "Domino".to_string(),
+            color: 3,
+            cells: vec![(0, 0), (1, 0)],
+            rotation_center: (2, 2),
+        }]);
+        let piece_type = PieceType::Custom(0);
+        assert_eq!(sorted_cells(&domino, piece_type, 1), [(1, 0), (1, 1)]);
+        assert_eq!(sorted_cells(&domino, piece_type, 2), [(0, 1), (1, 1)]);
+        assert_eq!(sorted_cells(&domino, piece_type, 3), [(0, 0), (0, 1)]);
+        // Фігури з іншого набору на полі немає
+        assert!(piece_cells(&domino, PieceType::Custom(1), 0).is_empty());
+    }
+
+    #[test]
+    fn wide_pieces_spawn_inside_the_board() {
+        let spawned = |width, piece_type| {
+            let state = GameState::new(RuleSet {
+                width,
+                pieces: PieceSet::Pentominoes,
+                ..RuleSet::default()
+            });
+            let piece = spawn_piece(&state, piece_type);
+            assert!(is_valid_move(&state, &piece));
+            piece.position.x
+        };
+        let i5 = PieceType::Custom(2);
+        assert_eq!(spawned(10, i5), 4);
+        // На вузькому полі I5 зсувається до лівого краю
+        assert_eq!(spawned(5, i5), 0);
+        assert_eq!(spawned(5, PieceType::Custom(13)), 1);
+
+        let mut state = GameState::new(RuleSet {
+            pieces: PieceSet::Triominoes,
+            ..RuleSet::default()
+        });
+        for _ in 0..20 {
+            let piece = generate_new_piece(&mut state).unwrap();
+            assert!(matches!(piece.piece_type, PieceType::Custom(0 | 1)));
+        }
+    }
+
+    // Нижні `rows` рядків заповнені, крім стовпців `gap`; над проміжком висить I
+    fn clear_setup(rows: usize, gap: std::ops::Range<usize>, rotation: u8) -> GameState {
+        let mut state = GameState::new(RuleSet::default());
+        state.current_piece = generate_new_piece(&mut state);
+        let height = state.board.len();
+        for row in &mut state.board[height - rows..] {
+            row.fill(Some(PieceType::Garbage));
+            row[gap.clone()].fill(None);
+        }
+        // Вертикальна I займає третій стовпець своєї рамки, горизонтальна - другий рядок
+        state.current_piece = Some(Piece {
+            piece_type: PieceType::I,
+            position: Position {
+                x: gap.start as i32 - if rotation == 1 { 2 } else { 0 },
+                y: 0,
+            },
+            rotation,
+        });
+        state
+    }
+
+    #[test]
+    fn emptied_board_is_a_perfect_clear() {
+        let mut state = clear_setup(1, 0..4, 0);
+        let result = apply_action(&mut state, &GameAction::Drop, 1).unwrap();
+        assert!(result.perfect_clear);
+        assert_eq!(state.perfect_clears, 1);
+        assert_eq!(state.score, 100 + 800);
+        assert!(state.board.iter().flatten().all(Option::is_none));
+
+        let mut state = clear_setup(4, 9..10, 1);
+        let result = apply_action(&mut state, &GameAction::Drop, 1).unwrap();
+        assert!(result.perfect_clear);
+        assert_eq!(state.score, 400 + 2000);
+        assert!(state.back_to_back);
+
+        // Клітинка, що лишилася над очищеним рядком, - не perfect clear
+        let mut state = clear_setup(1, 0..4, 0);
+        let height = state.board.len();
+        state.board[height - 2][9] = Some(PieceType::Garbage);
+        let result = apply_action(&mut state, &GameAction::Drop, 1).unwrap();
+        assert!(!result.perfect_clear);
+        assert_eq!(state.perfect_clears, 0);
+        assert_eq!(state.score, 100);
+    }
+
+    #[test]
+    fn back_to_back_tetris_perfect_clear_earns_more() {
+        let mut state = clear_setup(4, 9..10, 1);
+        state.back_to_back = true;
+        apply_action(&mut state, &GameAction::Drop, 1).unwrap();
+        assert_eq!(
+            state.score,
+            400 + state.rules.scoring.back_to_back_perfect_clear
+        );
+
+        // Бонус множиться на рівень разом з очками за лінії
+        let mut state = clear_setup(4, 9..10, 1);
+        state.rules = RuleSet {
+            start_level: 3,
+            ..RuleSet::guideline()
+        };
+        state.back_to_back = true;
+        apply_action(&mut state, &GameAction::Drop, 1).unwrap();
+        assert_eq!(state.score, (800 + 3200) * 3);
+    }
+
+    fn timed_state(mode: GameMode, dig_rows: u8) -> GameState {
+        let mut state = GameState {
+            mode,
+            seed: 23,
+            ..GameState::new(RuleSet {
+                dig_rows,
+                ..RuleSet::default()
+            })
+        };
+        prepare_board(&mut state);
+        state.current_piece = generate_new_piece(&mut state);
+        state
+    }
+
+    #[test]
+    fn dig_race_ends_with_the_last_garbage_row() {
+        let state = timed_state(GameMode::DigRace, 6);
+        assert_eq!(garbage::garbage_remaining(&state), 6);
+
+        // Вертикальна I над отвором єдиного рядка сміття
+        let mut state = timed_state(GameMode::DigRace, 1);
+        let bottom = state.board.last().unwrap();
+        let hole = bottom.iter().position(Option::is_none).unwrap() as i32;
+        state.current_piece = Some(Piece {
+            piece_type: PieceType::I,
+            position: Position { x: hole - 2, y: 0 },
+            rotation: 1,
+        });
+        let result = apply_action(&mut state, &GameAction::Drop, 1_000_000).unwrap();
+        assert_eq!(result.message, "Dig race finished");
+        assert!(state.game_over && state.current_piece.is_none());
+        assert_eq!((state.lines, state.garbage_cleared), (1, 1));
+    }
+
+    #[test]
+    fn survival_scores_time_and_raises_garbage() {
+        // Сміття піднімається кожні 5 секунд ігрового часу, рахунок - прожиті секунди
+        let mut state = timed_state(GameMode::Survival, 6);
+        apply_action(&mut state, &GameAction::MoveLeft, 1_000_000).unwrap();
+        apply_action(&mut state, &GameAction::MoveRight, 12_500_000).unwrap();
+        assert_eq!(state.garbage_risen, 2);
+        assert_eq!(garbage::garbage_remaining(&state), 2);
+        assert_eq!(state.elapsed_micros, 11_500_000);
+        assert_eq!(state.score, 11);
+        assert!(!state.game_over);
+    }
+
+    #[test]
+    fn survival_ends_when_garbage_reaches_the_top() {
+        let mut state = timed_state(GameMode::Survival, 6);
+        apply_action(&mut state, &GameAction::MoveLeft, 1_000_000).unwrap();
+        // Стовпчик до самої стелі: наступний рядок сміття виштовхне його за поле
+        let width = state.rules.width as usize;
+        for row in &mut state.board {
+            row[width - 1] = Some(PieceType::Garbage);
+        }
+        apply_action(&mut state, &GameAction::MoveLeft, 6_000_001).unwrap();
+        assert!(state.game_over);
+        assert_eq!(state.garbage_risen, 1);
+        assert_eq!(state.score, 5);
+    }
+}
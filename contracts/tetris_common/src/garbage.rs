@@ -0,0 +1,250 @@
+use crate::{random::next_seed, GameState, GarbageAttack, PieceType};
+
+// Окремі потоки випадковості для сміття розкопок та виживання
+const DIG_SALT: u64 = 0x6469_6767;
+const SURVIVAL_SALT: u64 = 0x7375_7276;
+
+// Кількість рядків сміття за очищені лінії (як у guideline versus)
+pub fn garbage_for_lines(lines_cleared: u32) -> u32 {
+    match lines_cleared {
+        0 | 1 => 0,
+        2 => 1,
+        3 => 2,
+        _ => 4,
+    }
+}
+
+pub fn queue_garbage(state: &mut GameState, attack: GarbageAttack) {
+    if attack.lines == 0 {
+        return;
+    }
+
+    state.incoming_garbage.push(GarbageAttack {
+        lines: attack.lines,
+        hole_column: attack.hole_column % state.rules.width,
+    });
+}
+
+// Гасить сміття в черзі вихідною атакою, повертає залишок атаки
+pub fn cancel_garbage(state: &mut GameState, mut outgoing: u32) -> u32 {
+    while outgoing > 0 && !state.incoming_garbage.is_empty() {
+        let pending = &mut state.incoming_garbage[0];
+        if pending.lines > outgoing {
+            pending.lines -= outgoing;
+            outgoing = 0;
+        } else {
+            outgoing -= pending.lines;
+            state.incoming_garbage.remove(0);
+        }
+    }
+    outgoing
+}
+
+// Вставляє всю чергу сміття на дошку; повертає true, якщо гра завершилась
+pub fn apply_incoming_garbage(state: &mut GameState, messiness: u8, seed: u64) -> bool {
+    let mut seed = seed;
+    for attack in std::mem::take(&mut state.incoming_garbage) {
+        if insert_garbage(state, attack.lines, attack.hole_column, messiness, seed) {
+            return true;
+        }
+        seed = next_seed(seed);
+    }
+    false
+}
+
+// Піднімає стек на `lines` рядків знизу. `messiness` (0-100) — ймовірність у відсотках,
+// що отвір у наступному рядку зміститься в іншу колонку.
+pub fn insert_garbage(
+    state: &mut GameState,
+    lines: u32,
+    hole_column: u8,
+    messiness: u8,
+    seed: u64,
+) -> bool {
+    let width = state.rules.width as usize;
+    let mut hole = hole_column as usize % width;
+    let mut seed = seed;
+
+    for row in 0..lines {
+        if row > 0 {
+            seed = next_seed(seed);
+            if seed % 100 < messiness as u64 {
+                // Зсуваємо отвір на 1..width-1 колонок, щоб він точно змінився
+                hole = (hole + 1 + (seed >> 32) as usize % (width - 1)) % width;
+            }
+        }
+
+        // Якщо верхній рядок зайнятий, стек виходить за межі дошки
+        if state.board[0].iter().any(|cell| cell.is_some()) {
+            state.game_over = true;
+            state.current_piece = None;
+            state.incoming_garbage.clear();
+            return true;
+        }
+
+        let mut row = vec![Some(PieceType::Garbage); width];
+        row[hole] = None;
+        state.board.remove(0);
+        state.board.push(row);
+        if let Some(stamps) = &mut state.lock_stamps {
+            stamps.remove(0);
+            stamps.push(vec![state.piece_count; width]);
+        }
+    }
+
+    false
+}
+
+// Заповнює низ поля рядками сміття для перегонів розкопок; отвір у кожному рядку
+// в іншій колонці, ніж у сусідньому
+pub fn setup_dig(state: &mut GameState) {
+    let seed = next_seed(state.seed ^ DIG_SALT);
+    let hole_column = (seed % state.rules.width as u64) as u8;
+    insert_garbage(
+        state,
+        state.rules.dig_rows as u32,
+        hole_column,
+        100,
+        next_seed(seed),
+    );
+}
+
+// Один рядок сміття для виживання; отвір залежить від зерна та номера рядка
+pub fn rise_one(state: &mut GameState) -> bool {
+    let seed = next_seed(state.seed ^ next_seed(state.garbage_risen as u64) ^ SURVIVAL_SALT);
+    state.garbage_risen += 1;
+    insert_garbage(state, 1, (seed % state.rules.width as u64) as u8, 0, seed)
+}
+
+// Скільки рядків на полі ще містять сміття
+pub fn garbage_remaining(state: &GameState) -> u32 {
+    state
+        .board
+        .iter()
+        .filter(|row| row.contains(&Some(PieceType::Garbage)))
+        .count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    fn attack(lines: u32, hole_column: u8) -> GarbageAttack {
+        GarbageAttack { lines, hole_column }
+    }
+
+    fn holes(row: &[Option<PieceType>]) -> Vec<usize> {
+        (0..row.len()).filter(|&x| row[x].is_none()).collect()
+    }
+
+    #[test]
+    fn outgoing_attack_cancels_queue() {
+        let mut state = GameState::new(RuleSet::default());
+        queue_garbage(&mut state, attack(2, 0));
+        queue_garbage(&mut state, attack(3, 1));
+
+        // Атака гасить першу чергу повністю і частину другої
+        assert_eq!(cancel_garbage(&mut state, 3), 0);
+        assert_eq!(state.incoming_garbage.len(), 1);
+        assert_eq!(state.incoming_garbage[0].lines, 2);
+
+        // Надлишок атаки повертається, щоб відправити його суперникові
+        assert_eq!(cancel_garbage(&mut state, 5), 3);
+        assert!(state.incoming_garbage.is_empty());
+        assert_eq!(cancel_garbage(&mut state, 4), 4);
+    }
+
+    #[test]
+    fn shifted_holes_stay_on_board() {
+        let mut state = GameState::new(RuleSet::default());
+        let width = state.rules.width as usize;
+        let lines = state.rules.height as u32 - 1;
+        // Отвір за межами дошки береться за модулем ширини
+        assert!(!insert_garbage(&mut state, lines, 255, 100, 42));
+
+        let rows = &state.board[1..];
+        for (row, next) in rows.iter().zip(&rows[1..]) {
+            assert_eq!(row.len(), width);
+            assert_eq!(holes(row).len(), 1);
+            // За повної безладності отвір щоразу переходить в іншу колонку
+            assert_ne!(holes(row), holes(next));
+        }
+        // Перший вставлений рядок підіймається найвище
+        assert_eq!(holes(&state.board[1]), vec![255 % width]);
+    }
+
+    #[test]
+    fn garbage_overflowing_top_ends_game() {
+        let mut state = GameState::new(RuleSet::default());
+        let height = state.rules.height as u32;
+        queue_garbage(&mut state, attack(1, 0));
+
+        // Поки верхній рядок порожній, стек ще вміщається
+        assert!(!insert_garbage(&mut state, height, 0, 0, 1));
+        assert!(!state.game_over);
+        assert!(insert_garbage(&mut state, 1, 0, 0, 1));
+        assert!(state.game_over);
+        assert!(state.current_piece.is_none());
+        assert!(state.incoming_garbage.is_empty());
+    }
+
+    #[test]
+    fn attack_grows_with_cleared_lines() {
+        let attacks: Vec<u32> = (0..=5).map(garbage_for_lines).collect();
+        assert_eq!(attacks, vec![0, 0, 1, 2, 4, 4]);
+    }
+
+    #[test]
+    fn queued_garbage_is_inserted_in_order() {
+        let mut state = GameState::new(RuleSet::default());
+        let width = state.rules.width as usize;
+        queue_garbage(&mut state, attack(0, 3));
+        assert!(state.incoming_garbage.is_empty());
+        queue_garbage(&mut state, attack(1, 2));
+        queue_garbage(&mut state, attack(2, width as u8 + 5));
+        assert_eq!(state.incoming_garbage[1].hole_column, 5);
+
+        assert!(!apply_incoming_garbage(&mut state, 0, 7));
+        assert!(state.incoming_garbage.is_empty());
+        assert_eq!(garbage_remaining(&state), 3);
+        // Без безладності отвір не зсувається в межах однієї атаки
+        let bottom = state.board.len() - 1;
+        assert_eq!(holes(&state.board[bottom - 2]), vec![2]);
+        assert_eq!(holes(&state.board[bottom - 1]), vec![5]);
+        assert_eq!(holes(&state.board[bottom]), vec![5]);
+        assert!(state.board[bottom - 3].iter().all(Option::is_none));
+        assert_eq!(state.board[bottom].len(), width);
+    }
+
+    #[test]
+    fn overflow_stops_the_queue() {
+        let mut state = GameState::new(RuleSet::default());
+        let height = state.rules.height as u32;
+        queue_garbage(&mut state, attack(height, 0));
+        queue_garbage(&mut state, attack(1, 0));
+        queue_garbage(&mut state, attack(1, 0));
+
+        assert!(apply_incoming_garbage(&mut state, 0, 7));
+        assert!(state.game_over);
+        assert!(state.incoming_garbage.is_empty());
+    }
+
+    #[test]
+    fn garbage_lifts_lock_stamps() {
+        let mut state = GameState::new(RuleSet {
+            visibility: crate::rules::Visibility::Invisible { after_pieces: 1 },
+            ..RuleSet::default()
+        });
+        let bottom = state.board.len() - 1;
+        state.piece_count = 4;
+        state.board[bottom][0] = Some(PieceType::T);
+        state.lock_stamps.as_mut().unwrap()[bottom][0] = 2;
+
+        assert!(!insert_garbage(&mut state, 1, 1, 0, 7));
+        let stamps = state.lock_stamps.as_ref().unwrap();
+        assert_eq!(state.board[bottom - 1][0], Some(PieceType::T));
+        assert_eq!(stamps[bottom - 1][0], 2);
+        assert_eq!(stamps[bottom], vec![4; state.rules.width as usize]);
+    }
+}
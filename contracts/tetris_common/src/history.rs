@@ -0,0 +1,270 @@
+use linera_sdk::base::{BlockHeight, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::{engine, GameMode, GameState, GhostReference, VersusResult};
+
+// Найбільша кількість записів на одній сторінці історії
+pub const MAX_PAGE_SIZE: u32 = 50;
+
+// Момент початку поточної гри
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct GameStart {
+    pub timestamp: Timestamp,
+    pub block_height: BlockHeight,
+}
+
+// Чим завершилася гра
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum EndReason {
+    #[default]
+    Finished,
+    Forfeited,
+    // Гру покинуто: забагато часу без дій або на паузі
+    Abandoned,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GameRecord {
+    pub mode: GameMode,
+    pub seed: u64,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub piece_count: u32,
+    pub duration_micros: u64,
+    pub started_at: Timestamp,
+    pub ended_at: Timestamp,
+    pub start_block: BlockHeight,
+    pub end_block: BlockHeight,
+    pub result: Option<VersusResult>,
+    pub end_reason: EndReason,
+    pub perfect_clears: u32,
+    // Очищені рядки зі сміттям та ігровий час без пауз
+    pub garbage_cleared: u32,
+    pub elapsed_micros: u64,
+    // Ключ збереженого запису гри
+    pub replay_id: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct HistoryStats {
+    pub games: u32,
+    pub best_score: u32,
+    pub average_score: u32,
+    pub total_score: u64,
+    pub total_lines: u64,
+    pub total_perfect_clears: u64,
+}
+
+impl GameRecord {
+    pub fn new(
+        state: &GameState,
+        start: GameStart,
+        ended_at: Timestamp,
+        end_block: BlockHeight,
+        end_reason: EndReason,
+        replay_id: u32,
+    ) -> Self {
+        Self {
+            mode: state.mode,
+            seed: state.seed,
+            score: state.score,
+            lines: state.lines,
+            level: engine::level(&state.rules, state.lines),
+            piece_count: state.piece_count,
+            duration_micros: ended_at.delta_since(start.timestamp).as_micros(),
+            started_at: start.timestamp,
+            ended_at,
+            start_block: start.block_height,
+            end_block,
+            result: state.versus_result,
+            end_reason,
+            perfect_clears: state.perfect_clears,
+            garbage_cleared: state.garbage_cleared,
+            elapsed_micros: state.elapsed_micros,
+            replay_id,
+        }
+    }
+}
+
+impl HistoryStats {
+    pub fn add(&mut self, record: &GameRecord) {
+        self.games += 1;
+        self.best_score = self.best_score.max(record.score);
+        self.total_score += record.score as u64;
+        self.total_lines += record.lines as u64;
+        self.total_perfect_clears += record.perfect_clears as u64;
+        self.average_score = (self.total_score / self.games as u64) as u32;
+    }
+}
+
+// Повертає діапазон індексів сторінки, починаючи з найновіших записів
+pub fn page_range(total: u32, offset: u32, limit: u32) -> std::ops::Range<usize> {
+    let end = total.saturating_sub(offset);
+    let start = end.saturating_sub(limit.min(MAX_PAGE_SIZE));
+    start as usize..end as usize
+}
+
+// Номер збереженого запису гри для привида з історії гравця; записи інших
+// гравців (виклики та передані записи) тут не шукаються
+pub fn ghost_replay_id(
+    records: &[GameRecord],
+    reference: &GhostReference,
+    seed: u64,
+    mode: GameMode,
+) -> Option<u32> {
+    match reference {
+        GhostReference::PersonalBest => records
+            .iter()
+            .filter(|record| record.seed == seed && record.mode == mode)
+            .max_by_key(|record| record.score)
+            .map(|record| record.replay_id),
+        GhostReference::Archived { replay_id } => records
+            .iter()
+            .any(|record| record.replay_id == *replay_id)
+            .then_some(*replay_id),
+        GhostReference::Challenge { .. } | GhostReference::Replay { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    fn record(score: u32, lines: u32, perfect_clears: u32) -> GameRecord {
+        let state = GameState {
+            score,
+            lines,
+            perfect_clears,
+            seed: 7,
+            mode: GameMode::Marathon,
+            ..GameState::new(RuleSet::default())
+        };
+        let start = GameStart {
+            timestamp: Timestamp::from(1_000_000),
+            block_height: BlockHeight::from(3),
+        };
+        GameRecord::new(
+            &state,
+            start,
+            Timestamp::from(61_000_000),
+            BlockHeight::from(9),
+            EndReason::Finished,
+            0,
+        )
+    }
+
+    #[test]
+    fn pages_start_from_newest() {
+        assert_eq!(page_range(0, 0, 10), 0..0);
+        assert_eq!(page_range(25, 0, 10), 15..25);
+        assert_eq!(page_range(25, 10, 10), 5..15);
+        // Остання сторінка неповна
+        assert_eq!(page_range(25, 20, 10), 0..5);
+        assert_eq!(page_range(25, 25, 10), 0..0);
+        assert_eq!(page_range(25, 40, 10), 0..0);
+        assert_eq!(page_range(200, 0, u32::MAX), 150..200);
+    }
+
+    #[test]
+    fn stats_add_up() {
+        let mut stats = HistoryStats::default();
+        stats.add(&record(300, 4, 0));
+        stats.add(&record(1000, 10, 1));
+        stats.add(&record(200, 2, 0));
+
+        assert_eq!(
+            stats,
+            HistoryStats {
+                games: 3,
+                best_score: 1000,
+                average_score: 500,
+                total_score: 1500,
+                total_lines: 16,
+                total_perfect_clears: 1,
+            }
+        );
+        assert_eq!(record(0, 0, 0).duration_micros, 60_000_000);
+    }
+
+    #[test]
+    fn ghosts_resolve_to_archived_games() {
+        let mut records: Vec<GameRecord> = [(300, 7), (900, 8), (1200, 7), (500, 7)]
+            .into_iter()
+            .enumerate()
+            .map(|(replay_id, (score, seed))| GameRecord {
+                seed,
+                replay_id: replay_id as u32,
+                ..record(score, 0, 0)
+            })
+            .collect();
+        records[3].mode = GameMode::DigRace;
+
+        // Найкраща гра з тим самим зерном і режимом, а не найкраща взагалі
+        let best = GhostReference::PersonalBest;
+        assert_eq!(
+            ghost_replay_id(&records, &best, 7, GameMode::Marathon),
+            Some(2)
+        );
+        assert_eq!(
+            ghost_replay_id(&records, &best, 8, GameMode::Marathon),
+            Some(1)
+        );
+        assert_eq!(
+            ghost_replay_id(&records, &best, 9, GameMode::Marathon),
+            None
+        );
+        assert_eq!(ghost_replay_id(&[], &best, 7, GameMode::Marathon), None);
+
+        let archived = GhostReference::Archived { replay_id: 3 };
+        assert_eq!(
+            ghost_replay_id(&records, &archived, 0, GameMode::Survival),
+            Some(3)
+        );
+        let missing = GhostReference::Archived { replay_id: 4 };
+        assert_eq!(
+            ghost_replay_id(&records, &missing, 7, GameMode::Marathon),
+            None
+        );
+
+        let replay = GhostReference::Replay { replay: vec![1] };
+        assert_eq!(
+            ghost_replay_id(&records, &replay, 7, GameMode::Marathon),
+            None
+        );
+    }
+
+    #[test]
+    fn forfeited_game_is_archived() {
+        let mut state = GameState::new(RuleSet::default());
+        state.current_piece = engine::generate_new_piece(&mut state);
+        for timestamp in [1_000_000, 2_000_000] {
+            engine::apply_action(&mut state, &crate::GameAction::Drop, timestamp).unwrap();
+        }
+        // Здача завершує гру так само, як контракт
+        engine::apply_action(&mut state, &crate::GameAction::GameOver, 3_000_000).unwrap();
+
+        let start = GameStart {
+            timestamp: Timestamp::from(500_000),
+            block_height: BlockHeight::from(1),
+        };
+        let archived = GameRecord::new(
+            &state,
+            start,
+            Timestamp::from(3_000_000),
+            BlockHeight::from(4),
+            EndReason::Forfeited,
+            5,
+        );
+        assert_eq!(archived.end_reason, EndReason::Forfeited);
+        assert_eq!(archived.piece_count, state.piece_count);
+        assert_eq!(archived.duration_micros, 2_500_000);
+        assert_eq!(archived.elapsed_micros, 1_000_000);
+        assert_eq!(archived.replay_id, 5);
+
+        let mut stats = HistoryStats::default();
+        stats.add(&archived);
+        assert_eq!(stats.games, 1);
+    }
+}
@@ -1,6 +1,41 @@
-use linera_sdk::base::{ContractAbi, ServiceAbi};
+use linera_sdk::base::{ApplicationId, BytecodeId, ChainId, ContractAbi, ServiceAbi};
 use serde::{Deserialize, Serialize};
 
+pub mod achievements;
+pub mod cascade;
+pub mod challenge;
+pub mod codec;
+pub mod engine;
+pub mod garbage;
+pub mod history;
+pub mod lobby;
+pub mod pieces;
+pub mod practice;
+pub mod prizes;
+pub mod puzzle;
+pub mod random;
+pub mod replay;
+pub mod rating;
+pub mod royale;
+pub mod rules;
+pub mod spectate;
+pub mod tournament;
+pub mod visibility;
+mod state;
+
+pub use state::TetrisState;
+
+use achievements::AchievementStatus;
+use challenge::{Challenge, ChallengeOutcome};
+use history::{GameRecord, HistoryStats};
+use lobby::{LobbyAbi, LobbyParameters};
+use puzzle::{Puzzle, PuzzleAbi};
+use rating::RatingAbi;
+use royale::TargetingStrategy;
+use rules::{RuleSet, Visibility};
+use spectate::GameEvent;
+use tournament::TournamentAbi;
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub enum PieceType {
     I,
@@ -10,14 +45,100 @@ pub enum PieceType {
     Z,
     J,
     L,
+    Garbage,
+    // Одна клітинка, що вибухає при фіксації та очищає квадрат 3x3 навколо себе
+    Bomb,
+    // Фігура з номером у наборі правил (тріаміно, пентаміно або власні фігури)
+    Custom(u8),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum GameMode {
+    #[default]
+    Marathon,
+    Versus,
+    Royale,
+    Puzzle,
+    // Тренування perfect clear: гра починається з підготовленого низу поля,
+    // після кожного perfect clear з'являється наступний
+    PerfectClearPractice,
+    // Перегони розкопок: розчистити стартове сміття якнайшвидше
+    DigRace,
+    // Виживання: сміття піднімається через рівні проміжки часу
+    Survival,
+}
+
+impl GameMode {
+    pub fn is_multiplayer(self) -> bool {
+        matches!(self, GameMode::Versus | GameMode::Royale)
+    }
+
+    // Режими, де результатом є час гри; пауза в них заборонена
+    pub fn is_timed(self) -> bool {
+        matches!(self, GameMode::DigRace | GameMode::Survival)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum VersusResult {
+    Won,
+    Lost,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct GarbageAttack {
+    pub lines: u32,
+    pub hole_column: u8,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GameState {
-    pub board: [[Option<PieceType>; 10]; 20],
+    // Рядки зверху вниз, розмір задають правила
+    pub board: Vec<Vec<Option<PieceType>>>,
+    pub rules: RuleSet,
     pub score: u32,
+    pub lines: u32,
     pub current_piece: Option<Piece>,
     pub game_over: bool,
+    pub mode: GameMode,
+    pub opponent_chain: Option<ChainId>,
+    pub incoming_garbage: Vec<GarbageAttack>,
+    pub seed: u64,
+    pub piece_count: u32,
+    pub match_id: Option<u64>,
+    pub versus_result: Option<VersusResult>,
+    pub placement: Option<u32>,
+    // Чи був останній успішний рух поворотом (для T-спінів)
+    pub last_move_rotated: bool,
+    // Час початку поточної паузи, кількість використаних пауз і загальний час на паузі
+    pub paused_at: Option<u64>,
+    pub pauses_used: u32,
+    pub paused_micros: u64,
+    // Момент, до якого вже застосовано гравітацію (0 - гра ще не почалася)
+    pub gravity_at: u64,
+    // Коли фігура торкнулася опори та скільки разів відтоді відкладено фіксацію
+    pub lock_started_at: Option<u64>,
+    pub lock_resets: u32,
+    // Наступні фігури (стільки, скільки дозволяють правила) та відкладена фігура
+    pub next_pieces: Vec<PieceType>,
+    pub held_piece: Option<PieceType>,
+    // Відкладати можна лише раз за фігуру
+    pub hold_used: bool,
+    // Фіксована послідовність фігур головоломки; порожня - фігури з генератора
+    pub piece_sequence: Vec<PieceType>,
+    // Чи було останнє очищення складним (тетріс або T-спін) та кількість perfect clear
+    pub back_to_back: bool,
+    pub perfect_clears: u32,
+    // Очищені рядки, в яких було сміття (решта ліній - звичайні)
+    // та рядки сміття, що піднялися у виживанні
+    pub garbage_cleared: u32,
+    pub garbage_risen: u32,
+    // Час першої дії та ігровий час без пауз станом на останню дію
+    pub started_at: Option<u64>,
+    pub elapsed_micros: u64,
+    // Номер фігури, з якою кожна клітинка з'явилася на полі; ведеться лише
+    // для невидимих блоків
+    pub lock_stamps: Option<Vec<Vec<u32>>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -38,14 +159,149 @@ pub struct Operation {
     pub action: GameAction,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum GameAction {
     StartGame,
+    StartSeeded { mode: GameMode, seed: u64, tournament_id: Option<u64> },
     MoveLeft,
     MoveRight,
     Rotate,
     Drop,
     GameOver,
+    StartVersus { opponent_chain: ChainId },
+    MatchFound { opponent_chain: ChainId, seed: u64, match_id: u64 },
+    // Лобі скасовує матч, який суперник не зміг почати
+    CancelMatch { match_id: u64 },
+    CreateRoyale { max_players: u32 },
+    JoinRoyale { match_chain: ChainId },
+    StartRoyale,
+    SetTargeting { strategy: TargetingStrategy },
+    Subscribe { player_chain: ChainId },
+    Unsubscribe { player_chain: ChainId },
+    Challenge { to_chain: ChainId, mode: GameMode, seed: u64 },
+    PlayChallenge { challenger: ChainId, challenge_id: u64 },
+    DeclineChallenge { challenger: ChainId, challenge_id: u64 },
+    Hold,
+    Pause,
+    Resume,
+    // Здатися: гра завершується поразкою та потрапляє в історію
+    Forfeit,
+    // Спроба головоломки, опублікованої в застосунку головоломок
+    StartPuzzle { puzzle_id: u64, puzzle: Box<Puzzle> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Message {
+    SendGarbage { lines: u32, hole_column: u8 },
+    ToppedOut,
+    RoyaleJoin,
+    RoyaleStarted { seed: u64 },
+    RoyaleAttack { lines: u32 },
+    RoyaleToppedOut,
+    RoyaleTargeting { strategy: TargetingStrategy },
+    RoyalePlacement { placement: u32, badges: u32 },
+    Subscribe,
+    Unsubscribe,
+    GameEvent(GameEvent),
+    ChallengeIssued { challenge_id: u64, mode: GameMode, seed: u64 },
+    ChallengeResult { challenger: ChainId, challenge_id: u64, replay: Vec<u8> },
+    ChallengeDeclined { challenge_id: u64 },
+}
+
+// Обмеження пауз і простою за замовчуванням
+pub const DEFAULT_MAX_PAUSES: u32 = 3;
+pub const DEFAULT_MAX_PAUSE_MICROS: u64 = 5 * 60 * 1_000_000;
+pub const DEFAULT_IDLE_TIMEOUT_MICROS: u64 = 15 * 60 * 1_000_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TetrisParameters {
+    pub rating_application: Option<ApplicationId<RatingAbi>>,
+    // Лише застосунок з кодом лобі може починати та скасовувати рейтингові матчі.
+    // Лобі саме знає застосунок гри, тож тут потрібен лише код, опублікований заздалегідь
+    pub lobby_bytecode: Option<BytecodeId<LobbyAbi, LobbyParameters>>,
+    pub tournament_application: Option<ApplicationId<TournamentAbi>>,
+    pub puzzle_application: Option<ApplicationId<PuzzleAbi>>,
+    // Скільки разів і як довго можна ставити гру на паузу
+    pub max_pauses: Option<u32>,
+    pub max_pause_micros: Option<u64>,
+    // Гра без дій довше за цей час вважається покинутою
+    pub idle_timeout_micros: Option<u64>,
+    // Правила гри для всіх ланцюжків цього застосунку
+    #[serde(default)]
+    pub rules: RuleSet,
+}
+
+impl TetrisParameters {
+    pub fn max_pauses(&self) -> u32 {
+        self.max_pauses.unwrap_or(DEFAULT_MAX_PAUSES)
+    }
+
+    pub fn max_pause_micros(&self) -> u64 {
+        self.max_pause_micros.unwrap_or(DEFAULT_MAX_PAUSE_MICROS)
+    }
+
+    pub fn idle_timeout_micros(&self) -> u64 {
+        self.idle_timeout_micros.unwrap_or(DEFAULT_IDLE_TIMEOUT_MICROS)
+    }
+
+    pub fn can_pause(&self, state: &GameState) -> bool {
+        state.pauses_used < self.max_pauses()
+    }
+
+    // Гра вважається покинутою, якщо гравець не повернувся з паузи вчасно
+    // або надто довго нічого не робив
+    pub fn is_abandoned(&self, state: &GameState, last_activity: u64, now: u64) -> bool {
+        if state.game_over || state.current_piece.is_none() {
+            return false;
+        }
+        match state.paused_at {
+            Some(paused_at) => now.saturating_sub(paused_at) > self.max_pause_micros(),
+            None => now.saturating_sub(last_activity) > self.idle_timeout_micros(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Query {
+    GameState,
+    Replay,
+    VerifyReplay { replay: Vec<u8>, claimed_score: u32 },
+    // Дошка гравця, відновлена з подій, які отримав цей ланцюжок
+    Spectate { player_chain: ChainId },
+    // Завершені ігри гравця, від найновіших
+    History { offset: u32, limit: u32 },
+    HistoryStats,
+    ArchivedReplay { replay_id: u32 },
+    Achievements,
+    Challenges,
+    // Дошка суперника-привида на тій самій фігурі, що й поточна гра
+    Ghost { reference: GhostReference, piece_count: Option<u32> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum GhostReference {
+    // Найкраща власна гра з тим самим зерном і режимом
+    PersonalBest,
+    Archived { replay_id: u32 },
+    // Перевірений запис суперника у виклику
+    Challenge { challenger: ChainId, challenge_id: u64 },
+    Replay { replay: Vec<u8> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum QueryResponse {
+    GameState(Box<GameState>),
+    // Серіалізований запис поточної (або останньої) гри; порожньо, поки поле приховане
+    Replay(Option<Vec<u8>>),
+    Verification { valid: bool, score: Option<u32> },
+    Spectate(Option<Box<GameState>>),
+    History { total: u32, records: Vec<GameRecord> },
+    HistoryStats(HistoryStats),
+    ArchivedReplay(Option<Vec<u8>>),
+    Achievements(Vec<AchievementStatus>),
+    // Виклики з результатом очної зустрічі для цього ланцюжка
+    Challenges(Vec<(Challenge, Option<ChallengeOutcome>)>),
+    Ghost(Option<Box<GameState>>),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -99,6 +355,8 @@ pub const TETROMINOES: &[(PieceType, &[&[bool]])] = &[
     ]),
 ];
 
+pub const BOMB_SHAPE: &[&[bool]] = &[&[true]];
+
 pub struct TetrisContract;
 
 impl ContractAbi for TetrisContract {
@@ -107,17 +365,53 @@ impl ContractAbi for TetrisContract {
 }
 
 impl ServiceAbi for TetrisContract {
-    type Query = ();
-    type QueryResponse = GameState;
+    type Query = Query;
+    type QueryResponse = QueryResponse;
 }
 
 impl Default for GameState {
     fn default() -> Self {
+        Self::new(RuleSet::default())
+    }
+}
+
+impl GameState {
+    pub fn new(rules: RuleSet) -> Self {
+        let lock_stamps = matches!(rules.visibility, Visibility::Invisible { .. })
+            .then(|| vec![vec![0; rules.width as usize]; rules.height as usize]);
         Self {
-            board: [[None; 10]; 20],
+            board: vec![vec![None; rules.width as usize]; rules.height as usize],
+            rules,
             score: 0,
+            lines: 0,
             current_piece: None,
             game_over: false,
+            mode: GameMode::Marathon,
+            opponent_chain: None,
+            incoming_garbage: Vec::new(),
+            seed: 0,
+            piece_count: 0,
+            match_id: None,
+            versus_result: None,
+            placement: None,
+            last_move_rotated: false,
+            paused_at: None,
+            pauses_used: 0,
+            paused_micros: 0,
+            gravity_at: 0,
+            lock_started_at: None,
+            lock_resets: 0,
+            next_pieces: Vec::new(),
+            held_piece: None,
+            hold_used: false,
+            piece_sequence: Vec::new(),
+            back_to_back: false,
+            perfect_clears: 0,
+            garbage_cleared: 0,
+            garbage_risen: 0,
+            started_at: None,
+            elapsed_micros: 0,
+            lock_stamps,
         }
     }
 }
@@ -162,4 +456,72 @@ pub fn rotate_shape(shape: &[&[bool]], rotation: u8) -> Vec<Vec<bool>> {
         }
         _ => unreachable!(),
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60 * 1_000_000;
+
+    fn playing() -> GameState {
+        let mut state = GameState::new(RuleSet::default());
+        state.current_piece = engine::generate_new_piece(&mut state);
+        state
+    }
+
+    #[test]
+    fn pauses_are_limited() {
+        let parameters = TetrisParameters {
+            max_pauses: Some(2),
+            ..TetrisParameters::default()
+        };
+        let mut state = playing();
+        for timestamp in [1, 3] {
+            assert!(parameters.can_pause(&state));
+            engine::apply_action(&mut state, &GameAction::Pause, timestamp).unwrap();
+            engine::apply_action(&mut state, &GameAction::Resume, timestamp + 1).unwrap();
+        }
+        assert_eq!(state.pauses_used, 2);
+        assert!(!parameters.can_pause(&state));
+        assert!(!TetrisParameters::default().can_pause(&GameState {
+            pauses_used: DEFAULT_MAX_PAUSES,
+            ..playing()
+        }));
+    }
+
+    #[test]
+    fn long_pauses_are_abandoned() {
+        let parameters = TetrisParameters {
+            max_pause_micros: Some(10 * MINUTE),
+            ..TetrisParameters::default()
+        };
+        let mut state = playing();
+        engine::apply_action(&mut state, &GameAction::Pause, MINUTE).unwrap();
+
+        // Простій не рахується, поки гра на паузі, лише тривалість самої паузи
+        assert!(!parameters.is_abandoned(&state, 0, 11 * MINUTE));
+        assert!(parameters.is_abandoned(&state, 0, 11 * MINUTE + 1));
+        assert!(parameters.is_abandoned(&state, 11 * MINUTE, 11 * MINUTE + 1));
+    }
+
+    #[test]
+    fn idle_games_are_abandoned() {
+        let parameters = TetrisParameters::default();
+        let state = playing();
+        let last_activity = 5 * MINUTE;
+        let timeout = DEFAULT_IDLE_TIMEOUT_MICROS;
+
+        assert!(!parameters.is_abandoned(&state, last_activity, last_activity + timeout));
+        assert!(parameters.is_abandoned(&state, last_activity, last_activity + timeout + 1));
+
+        // Завершену або ще не почату гру покидати нічого
+        let finished = GameState {
+            game_over: true,
+            ..state.clone()
+        };
+        assert!(!parameters.is_abandoned(&finished, 0, u64::MAX));
+        let idle = GameState::new(RuleSet::default());
+        assert!(!parameters.is_abandoned(&idle, 0, u64::MAX));
+    }
+}
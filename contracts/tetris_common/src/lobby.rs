@@ -0,0 +1,267 @@
+use linera_sdk::{
+    base::{ApplicationId, ChainId, ContractAbi, ServiceAbi, Timestamp},
+    views::{linera_views, RegisterView, RootView, ViewStorageContext},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{random::next_seed, rating::RatingAbi, GameMode, TetrisContract};
+
+pub struct LobbyAbi;
+
+impl ContractAbi for LobbyAbi {
+    type Operation = LobbyOperation;
+    type Response = LobbyResponse;
+}
+
+impl ServiceAbi for LobbyAbi {
+    type Query = ();
+    type QueryResponse = LobbySnapshot;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LobbyParameters {
+    pub lobby_chain: ChainId,
+    pub tetris_application: ApplicationId<TetrisContract>,
+    // Рейтинг приймає звіти лише про матчі, зареєстровані лобі
+    pub rating_application: Option<ApplicationId<RatingAbi>>,
+    pub queue_timeout_micros: u64,
+    pub rating_window: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum LobbyOperation {
+    JoinQueue { mode: GameMode, rating: u32 },
+    LeaveQueue,
+    // Прибирає з черги гравців, які чекали надто довго
+    ExpireQueue,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LobbyResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum LobbyMessage {
+    JoinQueue {
+        mode: GameMode,
+        rating: u32,
+    },
+    LeaveQueue,
+    ExpireQueue,
+    MatchFound {
+        opponent_chain: ChainId,
+        seed: u64,
+        match_id: u64,
+    },
+    // Суперник не зміг почати гру
+    MatchCancelled {
+        match_id: u64,
+    },
+    QueueTimedOut,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct QueueEntry {
+    pub chain_id: ChainId,
+    pub mode: GameMode,
+    pub rating: u32,
+    pub joined_at: Timestamp,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum QueueStatus {
+    #[default]
+    Idle,
+    Searching {
+        mode: GameMode,
+    },
+    Matched {
+        opponent_chain: ChainId,
+        match_id: u64,
+    },
+    MatchCancelled {
+        match_id: u64,
+    },
+    TimedOut,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LobbySnapshot {
+    pub status: QueueStatus,
+    pub queue: Vec<QueueEntry>,
+}
+
+#[derive(RootView)]
+#[view(context = "ViewStorageContext")]
+pub struct LobbyState {
+    // Стан гравця на його власному ланцюжку
+    pub status: RegisterView<QueueStatus>,
+    // Черга та лічильник матчів на ланцюжку лобі
+    pub queue: RegisterView<Vec<QueueEntry>>,
+    pub next_match_id: RegisterView<u64>,
+}
+
+impl LobbyParameters {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.queue_timeout_micros == 0 {
+            return Err("Queue timeout must be positive");
+        }
+        // З нульовим вікном гравці з різним рейтингом ніколи не зустрінуться
+        if self.rating_window == 0 {
+            return Err("Rating window must be positive");
+        }
+        Ok(())
+    }
+}
+
+// Видаляє гравців, які чекають довше за `timeout_micros`, і повертає їх
+pub fn expire_entries(
+    queue: &mut Vec<QueueEntry>,
+    now: Timestamp,
+    timeout_micros: u64,
+) -> Vec<QueueEntry> {
+    let (expired, waiting) = queue
+        .drain(..)
+        .partition(|entry| now.micros().saturating_sub(entry.joined_at.micros()) > timeout_micros);
+    *queue = waiting;
+    expired
+}
+
+// Шукає суперника з тим самим режимом і найближчим рейтингом у межах вікна.
+// Вікно розширюється на `rating_window` за кожні 10 секунд очікування.
+pub fn find_opponent(
+    queue: &[QueueEntry],
+    player: &QueueEntry,
+    rating_window: u32,
+    now: Timestamp,
+) -> Option<usize> {
+    queue
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.chain_id != player.chain_id && entry.mode == player.mode)
+        .filter(|(_, entry)| {
+            let waited_secs = now.micros().saturating_sub(entry.joined_at.micros()) / 1_000_000;
+            let window = rating_window.saturating_mul(1 + (waited_secs / 10) as u32);
+            entry.rating.abs_diff(player.rating) <= window
+        })
+        .min_by_key(|(_, entry)| (entry.rating.abs_diff(player.rating), entry.joined_at))
+        .map(|(index, _)| index)
+}
+
+pub fn match_seed(match_id: u64, now: Timestamp) -> u64 {
+    next_seed(match_id ^ now.micros())
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::base::{BlockHeight, BytecodeId, MessageId};
+
+    use super::*;
+
+    const SECOND: u64 = 1_000_000;
+
+    fn entry(index: u32, mode: GameMode, rating: u32, joined_secs: u64) -> QueueEntry {
+        QueueEntry {
+            chain_id: ChainId::root(index),
+            mode,
+            rating,
+            joined_at: Timestamp::from(joined_secs * SECOND),
+        }
+    }
+
+    #[test]
+    fn stale_entries_expire() {
+        let mut queue = vec![
+            entry(1, GameMode::Versus, 1000, 0),
+            entry(2, GameMode::Versus, 1000, 20),
+            entry(3, GameMode::Versus, 1000, 30),
+        ];
+
+        let expired = expire_entries(&mut queue, Timestamp::from(50 * SECOND), 30 * SECOND);
+        assert_eq!(expired, vec![entry(1, GameMode::Versus, 1000, 0)]);
+        assert_eq!(queue.len(), 2);
+        // Рівно на межі тайм-ауту гравець ще чекає
+        assert!(expire_entries(&mut queue, Timestamp::from(50 * SECOND), 30 * SECOND).is_empty());
+    }
+
+    #[test]
+    fn closest_rating_in_same_mode_wins() {
+        let queue = vec![
+            entry(1, GameMode::Royale, 1000, 0),
+            entry(2, GameMode::Versus, 1080, 0),
+            entry(3, GameMode::Versus, 1040, 5),
+            entry(4, GameMode::Versus, 960, 0),
+        ];
+        let player = entry(5, GameMode::Versus, 1000, 5);
+        let now = Timestamp::from(5 * SECOND);
+
+        // Два суперники однаково близькі: першим іде той, хто чекає довше
+        assert_eq!(find_opponent(&queue, &player, 100, now), Some(3));
+        assert_eq!(find_opponent(&queue, &player, 20, now), None);
+        // Гравець не може потрапити сам на себе
+        let own = [entry(5, GameMode::Versus, 1000, 0)];
+        assert_eq!(find_opponent(&own, &player, 100, now), None);
+    }
+
+    #[test]
+    fn rating_window_widens_while_waiting() {
+        let queue = vec![entry(1, GameMode::Versus, 1250, 0)];
+        let player = entry(2, GameMode::Versus, 1000, 0);
+
+        assert_eq!(
+            find_opponent(&queue, &player, 100, Timestamp::from(0)),
+            None
+        );
+        assert_eq!(
+            find_opponent(&queue, &player, 100, Timestamp::from(19 * SECOND)),
+            None
+        );
+        // Після 20 секунд вікно втричі ширше за початкове
+        assert_eq!(
+            find_opponent(&queue, &player, 100, Timestamp::from(20 * SECOND)),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn match_seed_depends_on_match() {
+        let now = Timestamp::from(7 * SECOND);
+        assert_eq!(match_seed(1, now), match_seed(1, now));
+        assert_ne!(match_seed(1, now), match_seed(2, now));
+    }
+
+    #[test]
+    fn parameters_need_positive_timeout_and_window() {
+        let creation = MessageId {
+            chain_id: ChainId::root(0),
+            height: BlockHeight::from(0),
+            index: 0,
+        };
+        let parameters = LobbyParameters {
+            lobby_chain: ChainId::root(0),
+            tetris_application: ApplicationId {
+                bytecode_id: BytecodeId::new(creation),
+                creation,
+            }
+            .with_abi(),
+            rating_application: None,
+            queue_timeout_micros: 30 * SECOND,
+            rating_window: 100,
+        };
+        assert_eq!(parameters.validate(), Ok(()));
+
+        let no_timeout = LobbyParameters {
+            queue_timeout_micros: 0,
+            ..parameters.clone()
+        };
+        assert!(no_timeout.validate().is_err());
+
+        let no_window = LobbyParameters {
+            rating_window: 0,
+            ..parameters
+        };
+        assert!(no_window.validate().is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{PieceType, TETROMINOES};
+
+// Обмеження для власних наборів фігур
+pub const MAX_PIECES: usize = 32;
+pub const MAX_PIECE_CELLS: usize = 8;
+// Клітинки фігури мають уміщатися в рамку MAX_PIECE_BOX x MAX_PIECE_BOX
+pub const MAX_PIECE_BOX: u8 = 5;
+pub const MAX_PIECE_NAME: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PieceDefinition {
+    pub name: String,
+    // Ідентифікатор кольору для клієнта
+    pub color: u8,
+    // Клітинки (x, y) у початковому положенні, y спрямована вниз
+    pub cells: Vec<(u8, u8)>,
+    // Центр повороту в половинах клітинки: (3, 3) - центр клітинки (1, 1),
+    // (2, 2) - спільний кут клітинок (0, 0) та (1, 1)
+    pub rotation_center: (u8, u8),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+pub enum PieceSet {
+    #[default]
+    Tetrominoes,
+    Triominoes,
+    // 18 односторонніх пентаміно (дзеркальні вважаються різними), як у Pentris
+    Pentominoes,
+    Custom(Vec<PieceDefinition>),
+}
+
+// Клітинки фігури та центр її повороту
+pub type PieceShape<'a> = (&'a [(u8, u8)], (u8, u8));
+
+// Назва, колір, клітинки та центр повороту вбудованої фігури
+type BuiltinPiece = (&'static str, u8, &'static [(u8, u8)], (u8, u8));
+
+const TRIOMINOES: &[BuiltinPiece] = &[
+    ("I3", 1, &[(0, 1), (1, 1), (2, 1)], (3, 3)),
+    ("V3", 2, &[(0, 0), (0, 1), (1, 1)], (2, 2)),
+];
+
+const PENTOMINOES: &[BuiltinPiece] = &[
+    ("F", 1, &[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)], (3, 3)),
+    ("F'", 2, &[(0, 0), (1, 0), (1, 1), (2, 1), (1, 2)], (3, 3)),
+    ("I5", 3, &[(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)], (5, 5)),
+    ("L5", 4, &[(0, 0), (0, 1), (1, 1), (2, 1), (3, 1)], (4, 4)),
+    ("L5'", 5, &[(3, 0), (0, 1), (1, 1), (2, 1), (3, 1)], (4, 4)),
+    ("N", 6, &[(0, 0), (1, 0), (1, 1), (2, 1), (3, 1)], (4, 4)),
+    ("N'", 7, &[(2, 0), (3, 0), (0, 1), (1, 1), (2, 1)], (4, 4)),
+    ("P", 8, &[(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)], (3, 3)),
+    ("P'", 9, &[(0, 0), (1, 0), (0, 1), (1, 1), (1, 2)], (3, 3)),
+    ("T5", 10, &[(0, 0), (1, 0), (2, 0), (1, 1), (1, 2)], (3, 3)),
+    ("U", 11, &[(0, 0), (2, 0), (0, 1), (1, 1), (2, 1)], (3, 3)),
+    ("V", 12, &[(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)], (3, 3)),
+    ("W", 13, &[(0, 0), (0, 1), (1, 1), (1, 2), (2, 2)], (3, 3)),
+    ("X", 14, &[(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)], (3, 3)),
+    ("Y", 15, &[(1, 0), (0, 1), (1, 1), (2, 1), (3, 1)], (4, 4)),
+    ("Y'", 16, &[(2, 0), (0, 1), (1, 1), (2, 1), (3, 1)], (4, 4)),
+    ("Z5", 17, &[(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)], (3, 3)),
+    ("Z5'", 18, &[(1, 0), (2, 0), (1, 1), (0, 2), (1, 2)], (3, 3)),
+];
+
+fn builtin(pieces: &[BuiltinPiece]) -> Vec<PieceDefinition> {
+    pieces
+        .iter()
+        .map(|(name, color, cells, rotation_center)| PieceDefinition {
+            name: name.to_string(),
+            color: *color,
+            cells: cells.to_vec(),
+            rotation_center: *rotation_center,
+        })
+        .collect()
+}
+
+impl PieceSet {
+    pub fn len(&self) -> usize {
+        match self {
+            PieceSet::Tetrominoes => TETROMINOES.len(),
+            PieceSet::Triominoes => TRIOMINOES.len(),
+            PieceSet::Pentominoes => PENTOMINOES.len(),
+            PieceSet::Custom(pieces) => pieces.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Тип фігури з номером `index` у наборі
+    pub fn piece_type(&self, index: usize) -> PieceType {
+        match self {
+            PieceSet::Tetrominoes => TETROMINOES[index].0,
+            _ => PieceType::Custom(index as u8),
+        }
+    }
+
+    pub fn contains(&self, piece_type: PieceType) -> bool {
+        (0..self.len()).any(|index| self.piece_type(index) == piece_type)
+    }
+
+    // Клітинки та центр повороту фігури з набору (для тетраміно - None,
+    // їх повертає рушій за таблицею TETROMINOES)
+    pub fn shape(&self, index: usize) -> Option<PieceShape<'_>> {
+        match self {
+            PieceSet::Tetrominoes => None,
+            PieceSet::Triominoes => TRIOMINOES.get(index).map(|piece| (piece.2, piece.3)),
+            PieceSet::Pentominoes => PENTOMINOES.get(index).map(|piece| (piece.2, piece.3)),
+            PieceSet::Custom(pieces) => pieces
+                .get(index)
+                .map(|piece| (piece.cells.as_slice(), piece.rotation_center)),
+        }
+    }
+
+    // Опис усіх фігур набору для клієнта
+    pub fn definitions(&self) -> Vec<PieceDefinition> {
+        match self {
+            PieceSet::Tetrominoes => TETROMINOES
+                .iter()
+                .enumerate()
+                .map(|(index, (piece_type, shape))| {
+                    let size = shape.len() as u8;
+                    PieceDefinition {
+                        name: format!("{piece_type:?}"),
+                        color: index as u8 + 1,
+                        cells: shape
+                            .iter()
+                            .enumerate()
+                            .flat_map(|(y, row)| {
+                                row.iter()
+                                    .enumerate()
+                                    .filter(|(_, cell)| **cell)
+                                    .map(move |(x, _)| (x as u8, y as u8))
+                            })
+                            .collect(),
+                        rotation_center: (size, size),
+                    }
+                })
+                .collect(),
+            PieceSet::Triominoes => builtin(TRIOMINOES),
+            PieceSet::Pentominoes => builtin(PENTOMINOES),
+            PieceSet::Custom(pieces) => pieces.clone(),
+        }
+    }
+
+    // Найширша рамка фігур набору
+    pub fn max_box(&self) -> u8 {
+        self.definitions()
+            .iter()
+            .flat_map(|piece| piece.cells.iter().map(|(x, y)| x.max(y) + 1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let PieceSet::Custom(pieces) = self else {
+            return Ok(());
+        };
+        if pieces.is_empty() || pieces.len() > MAX_PIECES {
+            return Err("Piece set must have between 1 and 32 pieces");
+        }
+        for piece in pieces {
+            validate_piece(piece)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_piece(piece: &PieceDefinition) -> Result<(), &'static str> {
+    if piece.name.len() > MAX_PIECE_NAME {
+        return Err("Piece name is too long");
+    }
+    let cells = &piece.cells;
+    if cells.is_empty() || cells.len() > MAX_PIECE_CELLS {
+        return Err("Piece must have between 1 and 8 cells");
+    }
+    if cells
+        .iter()
+        .any(|(x, y)| *x >= MAX_PIECE_BOX || *y >= MAX_PIECE_BOX)
+    {
+        return Err("Piece does not fit into a 5x5 box");
+    }
+    if (1..cells.len()).any(|index| cells[..index].contains(&cells[index])) {
+        return Err("Piece has duplicate cells");
+    }
+
+    // Поворот на 90° залишає клітинки на сітці, лише якщо центр - середина клітинки або її кут
+    let (center_x, center_y) = piece.rotation_center;
+    if (center_x + center_y) % 2 != 0
+        || center_x > 2 * MAX_PIECE_BOX
+        || center_y > 2 * MAX_PIECE_BOX
+    {
+        return Err("Invalid rotation center");
+    }
+
+    // Усі клітинки мають бути з'єднані сторонами
+    let mut connected = vec![cells[0]];
+    let mut index = 0;
+    while index < connected.len() {
+        let (x, y) = connected[index];
+        for cell in cells {
+            if !connected.contains(cell) && x.abs_diff(cell.0) + y.abs_diff(cell.1) == 1 {
+                connected.push(*cell);
+            }
+        }
+        index += 1;
+    }
+    if connected.len() != cells.len() {
+        return Err("Piece cells must be connected");
+    }
+    Ok(())
+}
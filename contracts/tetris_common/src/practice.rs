@@ -0,0 +1,31 @@
+use crate::{random, GameState, PieceType};
+
+// Окремий потік випадковості для підготовлених полів
+const PRACTICE_SALT: u64 = 0x7063_7072;
+
+// Порожній прямокутник (ширина, висота) внизу поля; його площа кратна чотирьом,
+// тож тетраміно можуть заповнити його повністю
+const SETUPS: [(u8, u8); 4] = [(4, 2), (2, 4), (3, 4), (4, 4)];
+
+// Заповнює низ поля сміттям, залишаючи прямокутник для perfect clear. Поле залежить
+// від зерна гри та кількості вже зроблених perfect clear, тож запис відтворюється.
+pub fn setup_board(state: &mut GameState) {
+    let hash = random::next_seed(
+        state.seed ^ random::next_seed(state.perfect_clears as u64) ^ PRACTICE_SALT,
+    );
+    let (gap_width, rows) = SETUPS[(hash % SETUPS.len() as u64) as usize];
+    let gap_start = (random::next_seed(hash) % (state.rules.width - gap_width + 1) as u64) as usize;
+    let gap = gap_start..gap_start + gap_width as usize;
+    let first_row = state.board.len() - rows as usize;
+
+    for (y, row) in state.board.iter_mut().enumerate() {
+        for (x, cell) in row.iter_mut().enumerate() {
+            *cell = (y >= first_row && !gap.contains(&x)).then_some(PieceType::Garbage);
+        }
+    }
+    if let Some(stamps) = &mut state.lock_stamps {
+        stamps
+            .iter_mut()
+            .for_each(|row| row.fill(state.piece_count));
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use linera_sdk::{
+    abis::fungible::Account,
+    base::{Amount, ChainId},
+};
+
+use crate::tournament::Standing;
+
+// Рахунок токена, з якого виплачуються внески та призи
+pub trait PrizeLedger {
+    fn transfer(&mut self, amount: Amount, target: Account);
+}
+
+// Локальна заміна фунгібельного токена: тримає фонд і записує виплати
+#[derive(Debug, Default)]
+pub struct LocalLedger {
+    pub pool: Amount,
+    pub balances: BTreeMap<Account, Amount>,
+}
+
+impl LocalLedger {
+    pub fn deposit(&mut self, amount: Amount) {
+        self.pool.saturating_add_assign(amount);
+    }
+
+    pub fn balance(&self, account: &Account) -> Amount {
+        self.balances.get(account).copied().unwrap_or_default()
+    }
+}
+
+impl PrizeLedger for LocalLedger {
+    fn transfer(&mut self, amount: Amount, target: Account) {
+        self.pool
+            .try_sub_assign(amount)
+            .expect("Prize pool is insufficient");
+        self.balances
+            .entry(target)
+            .or_default()
+            .saturating_add_assign(amount);
+    }
+}
+
+// Ділить фонд за місцями. Частки ненагороджених місць перерозподіляються між
+// зайнятими, а гравці з однаковим рахунком ділять призи місць, які вони займають.
+pub fn prize_shares(standings: &[Standing], pool: Amount, split: &[u32]) -> Vec<(ChainId, Amount)> {
+    let pool = u128::from(pool);
+    let filled = split.len().min(standings.len());
+    let total_percent: u128 = split[..filled].iter().map(|percent| *percent as u128).sum();
+    if pool == 0 || total_percent == 0 {
+        return Vec::new();
+    }
+
+    let position_prize = |position: usize| {
+        if position < filled {
+            pool * split[position] as u128 / total_percent
+        } else {
+            0
+        }
+    };
+
+    let mut shares: Vec<(ChainId, u128)> = Vec::new();
+    let mut position = 0;
+    while position < standings.len() {
+        let rank = standings[position].rank;
+        let group_len = standings[position..]
+            .iter()
+            .take_while(|standing| standing.rank == rank)
+            .count();
+        let group_total: u128 = (position..position + group_len).map(position_prize).sum();
+        let share = group_total / group_len as u128;
+        let mut remainder = group_total - share * group_len as u128;
+
+        for standing in &standings[position..position + group_len] {
+            let mut amount = share;
+            if remainder > 0 {
+                amount += 1;
+                remainder -= 1;
+            }
+            if amount > 0 {
+                shares.push((standing.player, amount));
+            }
+        }
+        position += group_len;
+    }
+
+    // Залишок від округлення дістається першому місцю
+    let paid: u128 = shares.iter().map(|(_, amount)| amount).sum();
+    if let Some((_, first)) = shares.first_mut() {
+        *first += pool - paid;
+    }
+
+    shares
+        .into_iter()
+        .map(|(player, amount)| (player, Amount::from_attos(amount)))
+        .collect()
+}
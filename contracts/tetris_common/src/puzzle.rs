@@ -0,0 +1,352 @@
+use linera_sdk::{
+    base::{ChainId, ContractAbi, ServiceAbi, Timestamp},
+    views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{self, StepResult},
+    replay::{Replay, ReplayInput, RULES_VERSION},
+    rules::RuleSet,
+    GameMode, GameState, PieceType,
+};
+
+// Межі розміру головоломки
+pub const MAX_PUZZLE_PIECES: usize = 100;
+pub const MAX_PUZZLE_TITLE: usize = 64;
+
+pub struct PuzzleAbi;
+
+impl ContractAbi for PuzzleAbi {
+    type Operation = PuzzleOperation;
+    type Response = PuzzleResponse;
+}
+
+impl ServiceAbi for PuzzleAbi {
+    type Query = PuzzleQuery;
+    type QueryResponse = PuzzleQueryResponse;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PuzzleParameters {
+    // Ланцюжок, на якому зберігаються головоломки та їхня статистика
+    pub puzzle_chain: ChainId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PuzzleGoal {
+    ClearLines(u32),
+    // Після очищення ліній дошка має стати порожньою
+    PerfectClear,
+    TSpinTriple,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Puzzle {
+    pub title: String,
+    pub rules: RuleSet,
+    // Початкова дошка, рядки зверху вниз
+    pub board: Vec<Vec<Option<PieceType>>>,
+    // Фігури по черзі; мету треба досягти, поки вони не скінчилися
+    pub pieces: Vec<PieceType>,
+    pub goal: PuzzleGoal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PuzzleOperation {
+    Publish(Puzzle),
+    SubmitAttempt { puzzle_id: u64, replay: Vec<u8> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PuzzleResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PuzzleMessage {
+    Publish(Puzzle),
+    SubmitAttempt { puzzle_id: u64, replay: Vec<u8> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct PuzzleStats {
+    pub attempts: u32,
+    pub solves: u32,
+    // Скільки різних гравців розв'язали головоломку
+    pub solvers: u32,
+    pub fewest_pieces: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PublishedPuzzle {
+    pub id: u64,
+    pub author: ChainId,
+    pub published_at: Timestamp,
+    pub puzzle: Puzzle,
+    pub stats: PuzzleStats,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PuzzleAttempt {
+    pub solved: bool,
+    // Скільки фігур зафіксовано до розв'язку або до кінця спроби
+    pub pieces_used: u32,
+    pub score: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PuzzleQuery {
+    Puzzles,
+    Puzzle { puzzle_id: u64 },
+    // Найменша кількість фігур, за яку гравець розв'язав головоломку
+    Solution { puzzle_id: u64, player: ChainId },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PuzzleQueryResponse {
+    Puzzles(Vec<PublishedPuzzle>),
+    Puzzle(Option<Box<PublishedPuzzle>>),
+    Solution(Option<u32>),
+}
+
+#[derive(RootView)]
+#[view(context = "ViewStorageContext")]
+pub struct PuzzleState {
+    pub puzzles: MapView<u64, PublishedPuzzle>,
+    pub next_puzzle_id: RegisterView<u64>,
+    pub solutions: MapView<(u64, ChainId), u32>,
+}
+
+impl PuzzleGoal {
+    // Чи досягнуто мету щойно виконаною дією
+    pub fn is_reached(&self, state: &GameState, step: &StepResult) -> bool {
+        match self {
+            PuzzleGoal::ClearLines(lines) => state.lines >= *lines,
+            PuzzleGoal::PerfectClear => step.perfect_clear,
+            PuzzleGoal::TSpinTriple => step.t_spin && step.largest_clear == 3,
+        }
+    }
+}
+
+impl Puzzle {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.title.is_empty() || self.title.len() > MAX_PUZZLE_TITLE {
+            return Err("Puzzle title must have between 1 and 64 bytes");
+        }
+        self.rules.validate()?;
+        if self.board.len() != self.rules.height as usize
+            || self
+                .board
+                .iter()
+                .any(|row| row.len() != self.rules.width as usize)
+        {
+            return Err("Board size does not match the rules");
+        }
+        if self.board.iter().any(|row| row.iter().all(Option::is_some)) {
+            return Err("Board must not have full rows");
+        }
+        if self.pieces.is_empty() || self.pieces.len() > MAX_PUZZLE_PIECES {
+            return Err("Puzzle must have between 1 and 100 pieces");
+        }
+        if !self
+            .pieces
+            .iter()
+            .all(|piece_type| self.rules.pieces.contains(*piece_type))
+        {
+            return Err("Puzzle pieces must belong to the piece set");
+        }
+        match self.goal {
+            PuzzleGoal::ClearLines(0) => return Err("Goal must clear at least one line"),
+            PuzzleGoal::TSpinTriple if !self.pieces.contains(&PieceType::T) => {
+                return Err("T-spin goal needs a T piece")
+            }
+            _ => {}
+        }
+        if self.initial_state().game_over {
+            return Err("First piece does not fit on the board");
+        }
+        Ok(())
+    }
+
+    // Стан на початку спроби: дошка автора та перша фігура з послідовності
+    pub fn initial_state(&self) -> GameState {
+        let mut state = GameState {
+            mode: GameMode::Puzzle,
+            board: self.board.clone(),
+            piece_sequence: self.pieces.clone(),
+            ..GameState::new(self.rules.clone())
+        };
+        state.current_piece = engine::generate_new_piece(&mut state);
+        state.game_over = state
+            .current_piece
+            .as_ref()
+            .is_none_or(|piece| !engine::is_valid_move(&state, piece));
+        state
+    }
+
+    // Відтворює спробу із запису та перевіряє мету; None - запис не від цієї головоломки
+    pub fn attempt(&self, replay_bytes: &[u8]) -> Option<PuzzleAttempt> {
+        let replay = Replay::from_bytes(replay_bytes).ok()?;
+        if replay.rules_version != RULES_VERSION
+            || replay.mode != GameMode::Puzzle
+            || replay.rules != self.rules
+        {
+            return None;
+        }
+
+        let mut state = self.initial_state();
+        for entry in &replay.entries {
+            // Сміття в головоломках не буває
+            let ReplayInput::Action(action) = &entry.input else {
+                return None;
+            };
+            if state.game_over {
+                break;
+            }
+            let step = engine::apply_action(&mut state, action, entry.timestamp_micros).ok()?;
+            if self.goal.is_reached(&state, &step) {
+                return Some(PuzzleAttempt {
+                    solved: true,
+                    pieces_used: pieces_used(&state),
+                    score: state.score,
+                });
+            }
+        }
+        Some(PuzzleAttempt {
+            solved: false,
+            pieces_used: pieces_used(&state),
+            score: state.score,
+        })
+    }
+}
+
+// Видані фігури, крім поточної та відкладеної
+fn pieces_used(state: &GameState) -> u32 {
+    state.piece_count - state.current_piece.is_some() as u32 - state.held_piece.is_some() as u32
+}
+
+impl PublishedPuzzle {
+    pub fn new(id: u64, author: ChainId, published_at: Timestamp, puzzle: Puzzle) -> Self {
+        Self {
+            id,
+            author,
+            published_at,
+            puzzle,
+            stats: PuzzleStats::default(),
+        }
+    }
+
+    // Враховує спробу гравця; `first_solve` - гравець розв'язав головоломку вперше
+    pub fn record_attempt(&mut self, attempt: &PuzzleAttempt, first_solve: bool) {
+        self.stats.attempts += 1;
+        if !attempt.solved {
+            return;
+        }
+        self.stats.solves += 1;
+        if first_solve {
+            self.stats.solvers += 1;
+        }
+        self.stats.fewest_pieces = Some(
+            self.stats
+                .fewest_pieces
+                .map_or(attempt.pieces_used, |fewest| {
+                    fewest.min(attempt.pieces_used)
+                }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameAction;
+
+    // Нижній рядок заповнено, крім чотирьох лівих клітинок, куди лягає I
+    fn line_puzzle() -> Puzzle {
+        let rules = RuleSet::default();
+        let mut board = vec![vec![None; rules.width as usize]; rules.height as usize];
+        for cell in board.last_mut().unwrap().iter_mut().skip(4) {
+            *cell = Some(PieceType::Garbage);
+        }
+        Puzzle {
+            title: "One line".to_string(),
+            rules,
+            board,
+            pieces: vec![PieceType::I, PieceType::O],
+            goal: PuzzleGoal::PerfectClear,
+        }
+    }
+
+    fn replay_of(puzzle: &Puzzle, actions: &[GameAction]) -> Vec<u8> {
+        let mut replay = Replay::new(0, GameMode::Puzzle, puzzle.rules.clone());
+        for action in actions {
+            replay.record_action(0, action.clone());
+        }
+        replay.to_bytes()
+    }
+
+    #[test]
+    fn solution_is_verified() {
+        let puzzle = line_puzzle();
+        assert_eq!(puzzle.validate(), Ok(()));
+
+        let solution = replay_of(
+            &puzzle,
+            &[
+                GameAction::MoveLeft,
+                GameAction::MoveLeft,
+                GameAction::MoveLeft,
+                GameAction::MoveLeft,
+                GameAction::Drop,
+            ],
+        );
+        assert_eq!(
+            puzzle.attempt(&solution),
+            Some(PuzzleAttempt {
+                solved: true,
+                pieces_used: 1,
+                // 100 за лінію та 800 бонусу за perfect clear
+                score: 900,
+            })
+        );
+
+        let miss = replay_of(&puzzle, &[GameAction::Drop, GameAction::Drop]);
+        let attempt = puzzle.attempt(&miss).unwrap();
+        assert!(!attempt.solved);
+        assert_eq!(attempt.pieces_used, 2);
+    }
+
+    #[test]
+    fn other_replays_are_rejected() {
+        let puzzle = line_puzzle();
+        let mut replay = Replay::new(0, GameMode::Marathon, puzzle.rules.clone());
+        replay.record_action(0, GameAction::Drop);
+        assert_eq!(puzzle.attempt(&replay.to_bytes()), None);
+
+        let mut published = PublishedPuzzle::new(0, ChainId::root(0), Timestamp::from(0), puzzle);
+        let solved = PuzzleAttempt {
+            solved: true,
+            pieces_used: 3,
+            score: 0,
+        };
+        published.record_attempt(&solved, true);
+        published.record_attempt(
+            &PuzzleAttempt {
+                pieces_used: 2,
+                ..solved
+            },
+            false,
+        );
+        assert_eq!(
+            published.stats,
+            PuzzleStats {
+                attempts: 2,
+                solves: 2,
+                solvers: 1,
+                fewest_pieces: Some(2),
+            }
+        );
+    }
+}
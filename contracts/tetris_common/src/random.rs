@@ -0,0 +1,54 @@
+use crate::{pieces::PieceSet, rules::Randomizer, PieceType};
+
+// Окремі потоки випадковості для мішків і бомб, щоб вони не збігалися з вибором фігур
+const BAG_SALT: u64 = 0x6261_6773;
+const BOMB_SALT: u64 = 0x626f_6d62;
+
+// splitmix64: простий детермінований генератор, однаковий на всіх валідаторах
+pub fn next_seed(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// Фігура з номером `index` у послідовності, яку задає `seed`. NES-генератор
+// додатково враховує попередню фігуру.
+pub fn piece_at(
+    randomizer: Randomizer,
+    pieces: &PieceSet,
+    seed: u64,
+    index: u32,
+    previous: Option<PieceType>,
+) -> PieceType {
+    let hash = next_seed(seed ^ next_seed(index as u64));
+    let count = pieces.len() as u64;
+    match randomizer {
+        Randomizer::Random => pieces.piece_type((hash % count) as usize),
+        Randomizer::SevenBag => {
+            // Перестановка Фішера-Єйтса для мішка, якому належить фігура
+            let bag = index as u64 / count;
+            let mut bag_seed = next_seed(seed ^ next_seed(bag) ^ BAG_SALT);
+            let mut order: Vec<usize> = (0..pieces.len()).collect();
+            for i in (1..order.len()).rev() {
+                bag_seed = next_seed(bag_seed);
+                order.swap(i, (bag_seed % (i as u64 + 1)) as usize);
+            }
+            pieces.piece_type(order[(index as u64 % count) as usize])
+        }
+        Randomizer::Nes => {
+            // Зайвий варіант або повтор попередньої фігури перекидаються один раз
+            let roll = (hash % (count + 1)) as usize;
+            if roll < pieces.len() && Some(pieces.piece_type(roll)) != previous {
+                pieces.piece_type(roll)
+            } else {
+                pieces.piece_type((next_seed(hash) % count) as usize)
+            }
+        }
+    }
+}
+
+// Чи стане фігура з номером `index` бомбою; `chance` у проміле
+pub fn is_bomb(seed: u64, index: u32, chance: u16) -> bool {
+    chance > 0 && next_seed(seed ^ next_seed(index as u64) ^ BOMB_SALT) % 1000 < chance as u64
+}
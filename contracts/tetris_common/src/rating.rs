@@ -0,0 +1,410 @@
+use linera_sdk::{
+    base::{BytecodeId, ChainId, ContractAbi, ServiceAbi, Timestamp},
+    views::{linera_views, MapView, RootView, ViewStorageContext},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    garbage,
+    lobby::{LobbyAbi, LobbyParameters},
+    replay::{self, Replay},
+    rules::RuleSet,
+    GameMode, TetrisContract, TetrisParameters,
+};
+
+pub const INITIAL_RATING: u32 = 1500;
+const K_FACTOR: f64 = 32.0;
+// Швидше за 10 фігур на секунду не грають навіть найкращі гравці; такий час
+// означає дії, зібрані в кілька блоків наперед
+const MIN_MICROS_PER_PIECE: u64 = 100_000;
+
+pub struct RatingAbi;
+
+impl ContractAbi for RatingAbi {
+    type Operation = RatingOperation;
+    type Response = ();
+}
+
+impl ServiceAbi for RatingAbi {
+    type Query = RatingQuery;
+    type QueryResponse = RatingQueryResponse;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RatingParameters {
+    pub rating_chain: ChainId,
+    // Код лобі, яке реєструє рейтингові матчі; саме лобі знає застосунок рейтингу
+    pub lobby_bytecode: Option<BytecodeId<LobbyAbi, LobbyParameters>>,
+    // Записи режимів на час приймаються лише від застосунку з кодом гри
+    pub tetris_bytecode: BytecodeId<TetrisContract, TetrisParameters>,
+    // Правила, за якими ведуться таблиці рекордів режимів на час
+    #[serde(default)]
+    pub rules: RuleSet,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct MatchResult {
+    pub match_id: u64,
+    pub winner: ChainId,
+    pub loser: ChainId,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RatingOperation {
+    // Лобі реєструє створений матч і його учасників
+    RegisterMatch {
+        match_id: u64,
+        players: (ChainId, ChainId),
+    },
+    ReportResult(MatchResult),
+    // Запис гри в режимі на час для таблиці рекордів
+    SubmitRecord {
+        replay: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RatingMessage {
+    RegisterMatch {
+        match_id: u64,
+        players: (ChainId, ChainId),
+    },
+    ReportResult(MatchResult),
+    SubmitRecord {
+        replay: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PlayerRating {
+    pub rating: u32,
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+impl Default for PlayerRating {
+    fn default() -> Self {
+        Self {
+            rating: INITIAL_RATING,
+            games: 0,
+            wins: 0,
+            losses: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RatingChange {
+    pub match_id: u64,
+    pub opponent: ChainId,
+    pub won: bool,
+    pub rating_before: u32,
+    pub rating_after: u32,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum MatchReport {
+    // Результат надіслав лише один гравець
+    Pending {
+        result: MatchResult,
+        reporter: ChainId,
+    },
+    Confirmed(MatchResult),
+    // Гравці надіслали різні результати, рейтинг не змінюється
+    Disputed,
+}
+
+// Перевірений результат перегонів розкопок або виживання
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ModeRecord {
+    pub mode: GameMode,
+    pub seed: u64,
+    // Час розчищення сміття або час виживання, без пауз
+    pub elapsed_micros: u64,
+    pub lines: u32,
+    pub garbage_cleared: u32,
+    pub piece_count: u32,
+    pub achieved_at: Timestamp,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RatingQuery {
+    Leaderboard {
+        limit: u32,
+    },
+    // Найкращі результати гравців у режимі на час; кожне зерно має окрему таблицю
+    ModeLeaderboard {
+        mode: GameMode,
+        seed: u64,
+        limit: u32,
+    },
+    Player {
+        chain_id: ChainId,
+    },
+    History {
+        chain_id: ChainId,
+    },
+    Match {
+        match_id: u64,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RatingQueryResponse {
+    Leaderboard(Vec<(ChainId, PlayerRating)>),
+    ModeLeaderboard(Vec<(ChainId, ModeRecord)>),
+    Player(PlayerRating),
+    History(Vec<RatingChange>),
+    Match(Option<MatchReport>),
+}
+
+#[derive(RootView)]
+#[view(context = "ViewStorageContext")]
+pub struct RatingState {
+    pub ratings: MapView<ChainId, PlayerRating>,
+    pub history: MapView<ChainId, Vec<RatingChange>>,
+    // Учасники матчів, зареєстрованих лобі
+    pub matches: MapView<u64, (ChainId, ChainId)>,
+    pub reports: MapView<u64, MatchReport>,
+    pub mode_records: MapView<(GameMode, u64, ChainId), ModeRecord>,
+}
+
+impl ModeRecord {
+    // Відтворює запис гри; None - гра не в режимі на час, за іншими правилами,
+    // перегони розкопок не завершено або час неправдоподібно малий
+    pub fn from_replay(
+        replay_bytes: &[u8],
+        rules: &RuleSet,
+        achieved_at: Timestamp,
+    ) -> Option<Self> {
+        let replay = Replay::from_bytes(replay_bytes).ok()?;
+        if !replay.mode.is_timed() || replay.rules != *rules {
+            return None;
+        }
+        let state = replay::simulate(&replay).ok()?;
+        let finished = match replay.mode {
+            GameMode::DigRace => garbage::garbage_remaining(&state) == 0,
+            _ => state.game_over,
+        };
+        let plausible = state.elapsed_micros > 0
+            && state.elapsed_micros >= state.piece_count as u64 * MIN_MICROS_PER_PIECE;
+        (finished && plausible).then_some(Self {
+            mode: replay.mode,
+            seed: replay.seed,
+            elapsed_micros: state.elapsed_micros,
+            lines: state.lines,
+            garbage_cleared: state.garbage_cleared,
+            piece_count: state.piece_count,
+            achieved_at,
+        })
+    }
+
+    // У перегонах розкопок кращий менший час, у виживанні - більший
+    pub fn beats(&self, other: &ModeRecord) -> bool {
+        match self.mode {
+            GameMode::DigRace => self.elapsed_micros < other.elapsed_micros,
+            _ => self.elapsed_micros > other.elapsed_micros,
+        }
+    }
+}
+
+// Elo: повертає нові рейтинги переможця та переможеного
+pub fn elo_update(winner: u32, loser: u32) -> (u32, u32) {
+    let expected = 1.0 / (1.0 + 10f64.powf((loser as f64 - winner as f64) / 400.0));
+    let delta = (K_FACTOR * (1.0 - expected)).round() as u32;
+    (winner + delta, loser.saturating_sub(delta))
+}
+
+// Звіт стосується саме тих гравців, яких лобі звело в матчі
+pub fn report_matches(players: (ChainId, ChainId), result: &MatchResult) -> bool {
+    (result.winner, result.loser) == players || (result.loser, result.winner) == players
+}
+
+// Зараховує звіт гравця; повертає підтверджений результат, коли обидва гравці згодні
+pub fn merge_report(
+    existing: Option<MatchReport>,
+    result: MatchResult,
+    reporter: ChainId,
+) -> MatchReport {
+    match existing {
+        None => MatchReport::Pending { result, reporter },
+        Some(MatchReport::Pending {
+            result: first,
+            reporter: first_reporter,
+        }) => {
+            if first_reporter == reporter {
+                MatchReport::Pending {
+                    result: first,
+                    reporter: first_reporter,
+                }
+            } else if first == result {
+                MatchReport::Confirmed(result)
+            } else {
+                MatchReport::Disputed
+            }
+        }
+        Some(report) => report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameAction;
+
+    fn result(winner: u32, loser: u32) -> MatchResult {
+        MatchResult {
+            match_id: 1,
+            winner: ChainId::root(winner),
+            loser: ChainId::root(loser),
+        }
+    }
+
+    #[test]
+    fn elo_follows_expected_score() {
+        // Рівні суперники: очікуваний результат 0.5, зміна K/2
+        assert_eq!(elo_update(1500, 1500), (1516, 1484));
+        // Фаворит на 400 пунктів очікує 10/11 перемоги й отримує мало
+        assert_eq!(elo_update(1900, 1500), (1903, 1497));
+        // Перемога аутсайдера коштує майже повне K
+        assert_eq!(elo_update(1500, 1900), (1529, 1871));
+        assert_eq!(elo_update(1500, 10), (1500, 10));
+        // Рейтинг переможеного не опускається нижче нуля
+        assert_eq!(elo_update(100, 10), (112, 0));
+    }
+
+    #[test]
+    fn agreeing_reports_confirm_match() {
+        let report = merge_report(None, result(1, 2), ChainId::root(1));
+        assert_eq!(
+            report,
+            MatchReport::Pending {
+                result: result(1, 2),
+                reporter: ChainId::root(1),
+            }
+        );
+        let report = merge_report(Some(report), result(1, 2), ChainId::root(2));
+        assert_eq!(report, MatchReport::Confirmed(result(1, 2)));
+        // Підтверджений матч уже не змінюється
+        assert_eq!(
+            merge_report(Some(report), result(2, 1), ChainId::root(2)),
+            report
+        );
+    }
+
+    #[test]
+    fn conflicting_reports_are_disputed() {
+        let report = merge_report(None, result(1, 2), ChainId::root(1));
+        let report = merge_report(Some(report), result(2, 1), ChainId::root(2));
+        assert_eq!(report, MatchReport::Disputed);
+        assert_eq!(
+            merge_report(Some(report), result(1, 2), ChainId::root(2)),
+            MatchReport::Disputed
+        );
+    }
+
+    #[test]
+    fn duplicate_report_keeps_first() {
+        let first = merge_report(None, result(1, 2), ChainId::root(1));
+        // Той самий гравець не може сам підтвердити чи змінити свій звіт
+        assert_eq!(
+            merge_report(Some(first), result(1, 2), ChainId::root(1)),
+            first
+        );
+        assert_eq!(
+            merge_report(Some(first), result(2, 1), ChainId::root(1)),
+            first
+        );
+    }
+
+    #[test]
+    fn reports_must_name_registered_players() {
+        let players = (ChainId::root(1), ChainId::root(2));
+        assert!(report_matches(players, &result(1, 2)));
+        assert!(report_matches(players, &result(2, 1)));
+        assert!(!report_matches(players, &result(1, 3)));
+        assert!(!report_matches(players, &result(1, 1)));
+    }
+
+    // Гра на виживання: кілька скидань у вказані моменти, потім здача
+    fn survival_replay(drops: &[u64], over_at: u64) -> Vec<u8> {
+        let mut replay = Replay::new(9, GameMode::Survival, RuleSet::default());
+        for &timestamp in drops {
+            replay.record_action(timestamp, GameAction::Drop);
+        }
+        replay.record_action(over_at, GameAction::GameOver);
+        replay.to_bytes()
+    }
+
+    #[test]
+    fn records_need_plausible_time() {
+        let rules = RuleSet::default();
+        let at = Timestamp::from(0);
+
+        let record =
+            ModeRecord::from_replay(&survival_replay(&[1, 3_000_001], 3_000_001), &rules, at);
+        assert_eq!(record.map(|record| record.elapsed_micros), Some(3_000_000));
+        // Усі дії в одному блоці дають нульовий час
+        assert_eq!(
+            ModeRecord::from_replay(&survival_replay(&[5, 5], 5), &rules, at),
+            None
+        );
+        // Сім фігур за п'яту частку секунди
+        let batched = survival_replay(&[1, 1, 1, 1, 1, 200_001], 200_001);
+        assert_eq!(ModeRecord::from_replay(&batched, &rules, at), None);
+
+        let other_rules = RuleSet {
+            hold: !rules.hold,
+            ..RuleSet::default()
+        };
+        let replay = survival_replay(&[1, 3_000_001], 3_000_001);
+        assert_eq!(ModeRecord::from_replay(&replay, &other_rules, at), None);
+    }
+
+    #[test]
+    fn records_need_a_finished_timed_game() {
+        let rules = RuleSet::default();
+        let at = Timestamp::from(0);
+        let given_up = |mode| {
+            let mut replay = Replay::new(9, mode, RuleSet::default());
+            replay.record_action(1, GameAction::Drop);
+            replay.record_action(3_000_001, GameAction::Drop);
+            replay.record_action(3_000_001, GameAction::GameOver);
+            replay.to_bytes()
+        };
+
+        assert!(ModeRecord::from_replay(&given_up(GameMode::Survival), &rules, at).is_some());
+        // Марафон не має таблиці рекордів, а здані перегони розкопок не завершені
+        assert_eq!(
+            ModeRecord::from_replay(&given_up(GameMode::Marathon), &rules, at),
+            None
+        );
+        assert_eq!(
+            ModeRecord::from_replay(&given_up(GameMode::DigRace), &rules, at),
+            None
+        );
+        assert_eq!(ModeRecord::from_replay(&[0xff], &rules, at), None);
+    }
+
+    #[test]
+    fn faster_digs_and_longer_survivals_win() {
+        let record = |mode, elapsed_micros| ModeRecord {
+            mode,
+            seed: 9,
+            elapsed_micros,
+            lines: 0,
+            garbage_cleared: 0,
+            piece_count: 10,
+            achieved_at: Timestamp::from(0),
+        };
+        let dig = |elapsed| record(GameMode::DigRace, elapsed);
+        assert!(dig(30_000_000).beats(&dig(40_000_000)));
+        assert!(!dig(40_000_000).beats(&dig(30_000_000)));
+        let survival = |elapsed| record(GameMode::Survival, elapsed);
+        assert!(survival(40_000_000).beats(&survival(30_000_000)));
+        // Рівний час не витісняє попередній рекорд
+        assert!(!survival(30_000_000).beats(&survival(30_000_000)));
+    }
+}
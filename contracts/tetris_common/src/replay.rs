@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    codec, engine, garbage, rules::RuleSet, GameAction, GameMode, GameState, GarbageAttack,
+};
+
+// Версія формату запису; перший байт серіалізованого запису
+pub const REPLAY_VERSION: u8 = 3;
+// Версія ігрових правил рушія; змінюється, коли симуляція дає інший результат
+pub const RULES_VERSION: u16 = 4;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ReplayInput {
+    Action(GameAction),
+    // Сміття, що надійшло від суперника
+    Garbage(GarbageAttack),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReplayEntry {
+    pub timestamp_micros: u64,
+    pub input: ReplayInput,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Replay {
+    pub version: u8,
+    pub rules_version: u16,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub rules: RuleSet,
+    pub entries: Vec<ReplayEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayError {
+    UnsupportedVersion(u8),
+    UnsupportedRules(u16),
+    Malformed,
+    // Дія з цим номером не могла бути застосована
+    InvalidAction(usize),
+}
+
+impl Replay {
+    pub fn new(seed: u64, mode: GameMode, rules: RuleSet) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            rules_version: RULES_VERSION,
+            seed,
+            mode,
+            rules,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn record_action(&mut self, timestamp_micros: u64, action: GameAction) {
+        self.entries.push(ReplayEntry {
+            timestamp_micros,
+            input: ReplayInput::Action(action),
+        });
+    }
+
+    pub fn record_garbage(&mut self, timestamp_micros: u64, attack: GarbageAttack) {
+        self.entries.push(ReplayEntry {
+            timestamp_micros,
+            input: ReplayInput::Garbage(attack),
+        });
+    }
+
+    // Компактне представлення запису для зберігання та повідомлень
+    pub fn to_bytes(&self) -> Vec<u8> {
+        codec::encode(self).expect("Failed to encode replay")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        codec::decode(bytes).map_err(|error| match error {
+            codec::CodecError::UnsupportedVersion(version) => {
+                ReplayError::UnsupportedVersion(version)
+            }
+            _ => ReplayError::Malformed,
+        })
+    }
+}
+
+// Повторно відтворює гру із запису та повертає кінцевий стан
+pub fn simulate(replay: &Replay) -> Result<GameState, ReplayError> {
+    simulate_to_piece(replay, u32::MAX)
+}
+
+// Відтворює гру, доки не з'явиться фігура з номером `piece_count`
+pub fn simulate_to_piece(replay: &Replay, piece_count: u32) -> Result<GameState, ReplayError> {
+    if replay.version != REPLAY_VERSION {
+        return Err(ReplayError::UnsupportedVersion(replay.version));
+    }
+    if replay.rules_version != RULES_VERSION {
+        return Err(ReplayError::UnsupportedRules(replay.rules_version));
+    }
+
+    // Головоломка починається з дошки автора, якої немає в записі;
+    // її перевіряє застосунок головоломок
+    if replay.rules.validate().is_err() || replay.mode == GameMode::Puzzle {
+        return Err(ReplayError::Malformed);
+    }
+
+    let mut state = GameState {
+        mode: replay.mode,
+        seed: replay.seed,
+        ..GameState::new(replay.rules.clone())
+    };
+    engine::prepare_board(&mut state);
+    state.current_piece = engine::generate_new_piece(&mut state);
+
+    for (index, entry) in replay.entries.iter().enumerate() {
+        if state.piece_count >= piece_count {
+            break;
+        }
+        match &entry.input {
+            ReplayInput::Action(action) => {
+                engine::apply_action(&mut state, action, entry.timestamp_micros)
+                    .map_err(|_| ReplayError::InvalidAction(index))?;
+            }
+            ReplayInput::Garbage(attack) => {
+                if engine::is_multiplayer(&state) && !state.game_over {
+                    garbage::queue_garbage(&mut state, *attack);
+                }
+            }
+        }
+    }
+
+    Ok(state)
+}
+
+// Перевіряє, що заявлений рахунок відтворюється із запису
+pub fn verify(replay: &Replay, claimed_score: u32) -> bool {
+    simulate(replay).is_ok_and(|state| state.score == claimed_score)
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::bcs;
+
+    use super::*;
+
+    // Грає так само, як контракт: записує лише дії, прийняті рушієм під час гри
+    fn play_live(mode: GameMode, rules: RuleSet, seed: u64) -> (GameState, Replay) {
+        let mut state = GameState {
+            mode,
+            seed,
+            ..GameState::new(rules.clone())
+        };
+        engine::prepare_board(&mut state);
+        state.current_piece = engine::generate_new_piece(&mut state);
+        let mut replay = Replay::new(seed, mode, rules);
+
+        let moves = [
+            GameAction::MoveLeft,
+            GameAction::Rotate,
+            GameAction::MoveRight,
+            GameAction::Hold,
+            GameAction::Drop,
+        ];
+        let mut timestamp = 5_000_000;
+        for step in 0..200u64 {
+            if state.game_over {
+                break;
+            }
+            let action = moves[(step * 7 % moves.len() as u64) as usize].clone();
+            // Часом гравець довго думає, і фігуру фіксує гравітація
+            timestamp += if step % 9 == 0 { 4_000_000 } else { 150_000 };
+            if engine::apply_action(&mut state, &action, timestamp).is_ok() {
+                replay.record_action(timestamp, action);
+            }
+        }
+        (state, replay)
+    }
+
+    fn same_state(left: &GameState, right: &GameState) -> bool {
+        bcs::to_bytes(left).unwrap() == bcs::to_bytes(right).unwrap()
+    }
+
+    #[test]
+    fn simulation_matches_live_game() {
+        for mode in [GameMode::Marathon, GameMode::DigRace, GameMode::Survival] {
+            let (live, replay) = play_live(mode, RuleSet::default(), 0xfeed);
+            assert!(live.game_over && live.piece_count > 3);
+
+            let restored = Replay::from_bytes(&replay.to_bytes()).unwrap();
+            assert_eq!(restored, replay);
+            let simulated = simulate(&restored).unwrap();
+            assert!(same_state(&simulated, &live), "{mode:?}");
+            assert!(verify(&restored, live.score));
+            assert!(!verify(&restored, live.score + 1));
+        }
+    }
+
+    #[test]
+    fn simulation_stops_at_piece() {
+        let (live, replay) = play_live(GameMode::Marathon, RuleSet::default(), 3);
+        let partial = simulate_to_piece(&replay, 4).unwrap();
+        assert_eq!(partial.piece_count, 4);
+        assert!(partial.piece_count < live.piece_count);
+    }
+
+    #[test]
+    fn foreign_versions_are_rejected() {
+        let (_, mut replay) = play_live(GameMode::Marathon, RuleSet::default(), 3);
+        replay.rules_version += 1;
+        assert_eq!(
+            simulate(&replay).unwrap_err(),
+            ReplayError::UnsupportedRules(RULES_VERSION + 1)
+        );
+        replay.version += 1;
+        assert_eq!(
+            simulate(&replay).unwrap_err(),
+            ReplayError::UnsupportedVersion(REPLAY_VERSION + 1)
+        );
+    }
+}
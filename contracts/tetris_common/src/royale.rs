@@ -0,0 +1,289 @@
+use linera_sdk::base::ChainId;
+use serde::{Deserialize, Serialize};
+
+use crate::random::next_seed;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum TargetingStrategy {
+    #[default]
+    Random,
+    // Відповідаємо тим, хто атакує нас
+    Attackers,
+    // Атакуємо гравця з найбільшою кількістю бейджів за нокаути
+    KoBadges,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoyalePlayer {
+    pub chain_id: ChainId,
+    pub alive: bool,
+    pub badges: u32,
+    pub strategy: TargetingStrategy,
+    pub target: Option<ChainId>,
+    pub last_attacker: Option<ChainId>,
+    pub placement: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoyaleMatch {
+    pub max_players: u32,
+    pub players: Vec<RoyalePlayer>,
+    pub started: bool,
+    pub finished: bool,
+}
+
+// Повідомлення про місце гравця, яке координатор має надіслати
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub chain_id: ChainId,
+    pub placement: u32,
+    pub badges: u32,
+}
+
+impl RoyaleMatch {
+    pub fn new(max_players: u32) -> Self {
+        Self {
+            max_players,
+            players: Vec::new(),
+            started: false,
+            finished: false,
+        }
+    }
+
+    pub fn join(&mut self, chain_id: ChainId) -> bool {
+        if self.started
+            || self.players.len() as u32 >= self.max_players
+            || self.player(chain_id).is_some()
+        {
+            return false;
+        }
+
+        self.players.push(RoyalePlayer {
+            chain_id,
+            alive: true,
+            badges: 0,
+            strategy: TargetingStrategy::Random,
+            target: None,
+            last_attacker: None,
+            placement: None,
+        });
+        true
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() as u32 >= self.max_players
+    }
+
+    pub fn alive_count(&self) -> u32 {
+        self.players.iter().filter(|player| player.alive).count() as u32
+    }
+
+    pub fn player(&self, chain_id: ChainId) -> Option<&RoyalePlayer> {
+        self.players
+            .iter()
+            .find(|player| player.chain_id == chain_id)
+    }
+
+    fn player_mut(&mut self, chain_id: ChainId) -> Option<&mut RoyalePlayer> {
+        self.players
+            .iter_mut()
+            .find(|player| player.chain_id == chain_id)
+    }
+
+    pub fn set_strategy(&mut self, chain_id: ChainId, strategy: TargetingStrategy) {
+        if let Some(player) = self.player_mut(chain_id) {
+            player.strategy = strategy;
+        }
+    }
+
+    // Бонус до атаки за бейджі, як у Tetris 99: +25% за кожен поріг 2/6/14/30
+    pub fn boosted_lines(&self, attacker: ChainId, lines: u32) -> u32 {
+        let badges = self.player(attacker).map_or(0, |player| player.badges);
+        let bonus_percent = [2, 6, 14, 30]
+            .iter()
+            .filter(|threshold| badges >= **threshold)
+            .count() as u32
+            * 25;
+        lines + lines * bonus_percent / 100
+    }
+
+    // Обирає ціль атаки за стратегією гравця та запам'ятовує її
+    pub fn choose_target(&mut self, attacker: ChainId, seed: u64) -> Option<ChainId> {
+        let strategy = self
+            .player(attacker)
+            .filter(|player| player.alive)?
+            .strategy;
+        let candidates: Vec<&RoyalePlayer> = self
+            .players
+            .iter()
+            .filter(|player| player.alive && player.chain_id != attacker)
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let random = candidates[(next_seed(seed) % candidates.len() as u64) as usize].chain_id;
+        let target = match strategy {
+            TargetingStrategy::Random => random,
+            TargetingStrategy::Attackers => {
+                let attackers: Vec<ChainId> = candidates
+                    .iter()
+                    .filter(|player| player.target == Some(attacker))
+                    .map(|player| player.chain_id)
+                    .collect();
+                if attackers.is_empty() {
+                    random
+                } else {
+                    attackers[(next_seed(seed ^ 1) % attackers.len() as u64) as usize]
+                }
+            }
+            TargetingStrategy::KoBadges => candidates
+                .iter()
+                .max_by_key(|player| player.badges)
+                .map_or(random, |player| player.chain_id),
+        };
+
+        if let Some(player) = self.player_mut(attacker) {
+            player.target = Some(target);
+        }
+        if let Some(player) = self.player_mut(target) {
+            player.last_attacker = Some(attacker);
+        }
+        Some(target)
+    }
+
+    // Вибуває гравець: повертає місця, про які треба повідомити
+    pub fn knock_out(&mut self, victim: ChainId) -> Vec<Placement> {
+        let placement = self.alive_count();
+        let Some(player) = self.player_mut(victim).filter(|player| player.alive) else {
+            return Vec::new();
+        };
+
+        player.alive = false;
+        player.placement = Some(placement);
+        let victim_badges = player.badges;
+        let last_attacker = player.last_attacker;
+
+        // Бейджі переможеного переходять до того, хто атакував його останнім
+        if let Some(attacker) = last_attacker.and_then(|chain_id| self.player_mut(chain_id)) {
+            if attacker.alive {
+                attacker.badges += 1 + victim_badges;
+            }
+        }
+
+        let mut placements = vec![Placement {
+            chain_id: victim,
+            placement,
+            badges: victim_badges,
+        }];
+
+        if self.alive_count() == 1 {
+            self.finished = true;
+            if let Some(winner) = self.players.iter_mut().find(|player| player.alive) {
+                winner.placement = Some(1);
+                placements.push(Placement {
+                    chain_id: winner.chain_id,
+                    placement: 1,
+                    badges: winner.badges,
+                });
+            }
+        }
+
+        placements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(index: u32) -> ChainId {
+        ChainId::root(index)
+    }
+
+    fn started_match(players: u32) -> RoyaleMatch {
+        let mut royale = RoyaleMatch::new(players);
+        for index in 1..=players {
+            assert!(royale.join(chain(index)));
+        }
+        royale.started = true;
+        royale
+    }
+
+    #[test]
+    fn random_target_is_another_live_player() {
+        let mut royale = started_match(4);
+        royale.knock_out(chain(2));
+
+        for seed in 0..20 {
+            let target = royale.choose_target(chain(1), seed).unwrap();
+            assert_ne!(target, chain(1));
+            assert_ne!(target, chain(2));
+            assert_eq!(royale.player(target).unwrap().last_attacker, Some(chain(1)));
+        }
+        assert_eq!(royale.choose_target(chain(2), 0), None);
+    }
+
+    #[test]
+    fn attackers_strategy_hits_back() {
+        let mut royale = started_match(4);
+        royale.set_strategy(chain(1), TargetingStrategy::Attackers);
+        royale.players[2].target = Some(chain(1));
+
+        for seed in 0..20 {
+            assert_eq!(royale.choose_target(chain(1), seed), Some(chain(3)));
+        }
+        // Без нападників ціль обирається випадково
+        royale.players[2].target = None;
+        assert!(royale.choose_target(chain(1), 0).is_some());
+    }
+
+    #[test]
+    fn badge_strategy_targets_leader() {
+        let mut royale = started_match(4);
+        royale.set_strategy(chain(1), TargetingStrategy::KoBadges);
+        royale.players[3].badges = 3;
+        royale.players[2].badges = 1;
+
+        assert_eq!(royale.choose_target(chain(1), 5), Some(chain(4)));
+        assert_eq!(royale.player(chain(1)).unwrap().target, Some(chain(4)));
+    }
+
+    #[test]
+    fn knockout_badges_go_to_last_attacker() {
+        let mut royale = started_match(4);
+        royale.players[1].badges = 2;
+        royale.players[1].last_attacker = Some(chain(3));
+
+        let placements = royale.knock_out(chain(2));
+        assert_eq!(
+            placements,
+            vec![Placement {
+                chain_id: chain(2),
+                placement: 4,
+                badges: 2,
+            }]
+        );
+        assert_eq!(royale.player(chain(3)).unwrap().badges, 3);
+        assert!(royale.knock_out(chain(2)).is_empty());
+
+        // Вибулий нападник бейджів не отримує
+        royale.players[3].last_attacker = Some(chain(2));
+        royale.knock_out(chain(4));
+        assert_eq!(royale.player(chain(2)).unwrap().badges, 2);
+        assert_eq!(royale.boosted_lines(chain(3), 4), 5);
+    }
+
+    #[test]
+    fn last_player_wins() {
+        let mut royale = started_match(2);
+        royale.players[1].last_attacker = Some(chain(1));
+
+        let placements = royale.knock_out(chain(2));
+        assert!(royale.finished);
+        assert_eq!(placements.len(), 2);
+        assert_eq!(placements[1].chain_id, chain(1));
+        assert_eq!(placements[1].placement, 1);
+        assert_eq!(placements[1].badges, 1);
+    }
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+
+use crate::pieces::PieceSet;
+
+// Межі розміру поля; ширина обмежена ще й тим, що колонка отвору сміття - це u8
+pub const MIN_BOARD_SIZE: u8 = 4;
+pub const MAX_BOARD_SIZE: u8 = 40;
+pub const MAX_PREVIEW: u8 = 6;
+// Сміття в режимі виживання піднімається не частіше ніж раз на цей час
+pub const MIN_GARBAGE_INTERVAL_MICROS: u64 = 500_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotationSystem {
+    // Поворот у рамці фігури без поштовхів
+    #[default]
+    Classic,
+    // Super Rotation System з таблицями поштовхів від стін
+    Srs,
+    // Arika Rotation System (спрощено): поштовх на клітинку вправо, потім вліво
+    Ars,
+    // Правобічний поворот NES без поштовхів; I, S та Z мають лише два положення
+    Nes,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Randomizer {
+    // Кожна фігура незалежна від попередніх
+    #[default]
+    Random,
+    // Усі фігури набору у випадковому порядку, потім наступний "мішок"
+    SevenBag,
+    // Як у NES: повтор попередньої фігури перекидається один раз
+    Nes,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LevelSystem {
+    // Рівні з 1, новий рівень кожні 10 ліній, швидкість за Tetris Guideline
+    #[default]
+    Guideline,
+    // Рівні з 0, переходи та швидкість NES аж до kill screen на 29 рівні
+    Nes,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClearGravity {
+    // Рядки над очищеними просто зсуваються вниз
+    #[default]
+    Naive,
+    // Незв'язані групи клітинок падають, доки не ляжуть, і можуть заповнити нові рядки;
+    // кожна наступна ланка ланцюжка множить очки на свій номер
+    Cascade,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    #[default]
+    Full,
+    // Зафіксовані клітинки зникають, щойно після них видано `after_pieces` фігур
+    Invisible {
+        after_pieces: u8,
+    },
+    // Видно лише `rows` верхніх рядків стека
+    Fog {
+        rows: u8,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Scoring {
+    // Очки за 1, 2, 3 та 4 лінії одночасно
+    pub line_clear: [u32; 4],
+    // Чи множити очки на поточний рівень
+    pub level_multiplier: bool,
+    // Бонус за очищення всього поля (perfect clear) 1-4 лініями
+    // та за perfect clear тетрісом одразу після іншого складного очищення
+    pub perfect_clear: [u32; 4],
+    pub back_to_back_perfect_clear: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    pub rotation: RotationSystem,
+    pub randomizer: Randomizer,
+    pub scoring: Scoring,
+    pub levels: LevelSystem,
+    pub start_level: u8,
+    // Фігура на опорі фіксується через цей час; рухи й повороти відкладають фіксацію,
+    // але не більше `max_lock_resets` разів за фігуру
+    pub lock_delay_micros: u64,
+    pub max_lock_resets: u32,
+    pub hold: bool,
+    // Скільки наступних фігур бачить гравець
+    pub preview: u8,
+    // Імовірність у проміле, що наступна фігура буде бомбою
+    pub bomb_chance: u16,
+    pub width: u8,
+    pub height: u8,
+    pub pieces: PieceSet,
+    // Рядків сміття на старті перегонів розкопок та інтервал підйому сміття у виживанні
+    pub dig_rows: u8,
+    pub garbage_interval_micros: u64,
+    pub clear_gravity: ClearGravity,
+    // Скільки поля бачить гравець; справжнє поле зберігає контракт
+    pub visibility: Visibility,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            rotation: RotationSystem::Classic,
+            randomizer: Randomizer::Random,
+            scoring: Scoring {
+                line_clear: [100, 200, 300, 400],
+                level_multiplier: false,
+                perfect_clear: [800, 1200, 1800, 2000],
+                back_to_back_perfect_clear: 3200,
+            },
+            levels: LevelSystem::Guideline,
+            start_level: 1,
+            lock_delay_micros: 500_000,
+            max_lock_resets: 15,
+            hold: false,
+            preview: 0,
+            bomb_chance: 0,
+            width: 10,
+            height: 20,
+            pieces: PieceSet::Tetrominoes,
+            dig_rows: 10,
+            garbage_interval_micros: 5_000_000,
+            clear_gravity: ClearGravity::Naive,
+            visibility: Visibility::Full,
+        }
+    }
+}
+
+impl RuleSet {
+    // Сучасні правила за Tetris Guideline
+    pub fn guideline() -> Self {
+        Self {
+            rotation: RotationSystem::Srs,
+            randomizer: Randomizer::SevenBag,
+            scoring: Scoring {
+                line_clear: [100, 300, 500, 800],
+                level_multiplier: true,
+                ..Self::default().scoring
+            },
+            hold: true,
+            preview: 5,
+            ..Self::default()
+        }
+    }
+
+    // Класичні правила NES: без відкладання й затримки фіксації, одна фігура в перегляді
+    pub fn nes() -> Self {
+        Self {
+            rotation: RotationSystem::Nes,
+            randomizer: Randomizer::Nes,
+            // У NES бонусу за perfect clear не було
+            scoring: Scoring {
+                line_clear: [40, 100, 300, 1200],
+                level_multiplier: true,
+                perfect_clear: [0; 4],
+                back_to_back_perfect_clear: 0,
+            },
+            levels: LevelSystem::Nes,
+            start_level: 0,
+            lock_delay_micros: 0,
+            max_lock_resets: 0,
+            hold: false,
+            preview: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let sizes = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+        if !sizes.contains(&self.width) || !sizes.contains(&self.height) {
+            return Err("Unsupported board size");
+        }
+        if self.preview > MAX_PREVIEW {
+            return Err("Too many preview pieces");
+        }
+        if self.bomb_chance > 1000 {
+            return Err("Bomb chance must be at most 1000 per mille");
+        }
+        if self.dig_rows == 0 || self.dig_rows >= self.height {
+            return Err("Dig rows must leave room on the board");
+        }
+        if self.garbage_interval_micros < MIN_GARBAGE_INTERVAL_MICROS {
+            return Err("Garbage interval is too short");
+        }
+        if self.visibility == (Visibility::Fog { rows: 0 }) {
+            return Err("Fog must reveal at least one row");
+        }
+        self.pieces.validate()?;
+        if self.pieces.max_box() > self.width.min(self.height) {
+            return Err("Pieces do not fit on the board");
+        }
+        Ok(())
+    }
+
+    pub fn line_clear_points(&self, lines: u32, level: u32) -> u32 {
+        if lines == 0 {
+            return 0;
+        }
+        let points = self.scoring.line_clear[(lines.min(4) - 1) as usize];
+        self.scale_by_level(points, level)
+    }
+
+    // Бонус за perfect clear; `back_to_back` - попереднє очищення теж було складним
+    pub fn perfect_clear_points(&self, lines: u32, back_to_back: bool, level: u32) -> u32 {
+        if lines == 0 {
+            return 0;
+        }
+        let points = if lines >= 4 && back_to_back {
+            self.scoring.back_to_back_perfect_clear
+        } else {
+            self.scoring.perfect_clear[(lines.min(4) - 1) as usize]
+        };
+        self.scale_by_level(points, level)
+    }
+
+    fn scale_by_level(&self, points: u32, level: u32) -> u32 {
+        if !self.scoring.level_multiplier {
+            return points;
+        }
+        // У NES рівні рахуються з нуля, тож множник - рівень + 1
+        match self.levels {
+            LevelSystem::Guideline => points * level,
+            LevelSystem::Nes => points * (level + 1),
+        }
+    }
+}
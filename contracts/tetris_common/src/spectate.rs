@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    replay::{self, Replay},
+    rules::{RuleSet, Visibility},
+    visibility, GameAction, GameMode, GameState, GarbageAttack,
+};
+
+// Найбільша кількість глядачів однієї гри
+pub const MAX_SPECTATORS: usize = 64;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum GameEvent {
+    // Запис поточної гри для нового глядача
+    Snapshot {
+        replay: Vec<u8>,
+    },
+    GameStarted {
+        seed: u64,
+        mode: GameMode,
+        rules: RuleSet,
+    },
+    ActionApplied {
+        timestamp_micros: u64,
+        action: GameAction,
+    },
+    GarbageReceived {
+        timestamp_micros: u64,
+        attack: GarbageAttack,
+    },
+    AchievementEarned {
+        id: String,
+    },
+    // Поле повністю очищено; на запис гри не впливає
+    PerfectClear {
+        lines: u32,
+        score: u32,
+    },
+    // Видимий стан гри з прихованим полем; її запис надсилається лише після завершення
+    VisibleState {
+        state: Box<GameState>,
+    },
+}
+
+// Гра, яку переглядає глядач
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WatchedGame {
+    pub replay: Replay,
+    // Останній видимий стан, поки гра з прихованим полем триває
+    pub visible: Option<GameState>,
+}
+
+impl WatchedGame {
+    // Стан гри таким, яким його бачить глядач
+    pub fn state(&self) -> Option<GameState> {
+        match &self.visible {
+            Some(state) => Some(state.clone()),
+            None => replay::simulate(&self.replay)
+                .ok()
+                .map(|state| visibility::visible_state(&state)),
+        }
+    }
+}
+
+// Знімок гри для нового глядача
+pub fn snapshot_event(state: &GameState, replay: &Replay) -> GameEvent {
+    match visibility::visible_replay(state, replay) {
+        Some(replay) => GameEvent::Snapshot { replay },
+        None => GameEvent::VisibleState {
+            state: Box::new(visibility::visible_state(state)),
+        },
+    }
+}
+
+// Подія про хід чи сміття; з таких подій можна відтворити приховане поле,
+// тож ігри з ним транслюються знімками
+pub fn progress_event(state: &GameState, replay: &Replay, event: GameEvent) -> GameEvent {
+    if state.rules.visibility == Visibility::Full {
+        event
+    } else {
+        snapshot_event(state, replay)
+    }
+}
+
+// Доповнює гру, яку переглядає глядач
+pub fn apply_event(watched: &mut WatchedGame, event: GameEvent) {
+    match event {
+        GameEvent::Snapshot { replay: bytes } => {
+            if let Ok(snapshot) = Replay::from_bytes(&bytes) {
+                watched.replay = snapshot;
+                watched.visible = None;
+            }
+        }
+        GameEvent::GameStarted { seed, mode, rules } => {
+            watched.replay = Replay::new(seed, mode, rules);
+            watched.visible = None;
+        }
+        GameEvent::ActionApplied {
+            timestamp_micros,
+            action,
+        } => watched.replay.record_action(timestamp_micros, action),
+        GameEvent::GarbageReceived {
+            timestamp_micros,
+            attack,
+        } => watched.replay.record_garbage(timestamp_micros, attack),
+        GameEvent::VisibleState { state } => watched.visible = Some(*state),
+        GameEvent::AchievementEarned { .. } | GameEvent::PerfectClear { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::bcs;
+
+    use super::*;
+    use crate::engine;
+
+    // Ходи, що ставлять фігуру після `turns` поворотів і зсуву на `shift` стовпців
+    fn placement(turns: usize, shift: i32) -> Vec<GameAction> {
+        let side = if shift < 0 {
+            GameAction::MoveLeft
+        } else {
+            GameAction::MoveRight
+        };
+        let mut actions = vec![GameAction::Rotate; turns];
+        actions.extend(std::iter::repeat_n(side, shift.unsigned_abs() as usize));
+        actions.push(GameAction::Drop);
+        actions
+    }
+
+    // Застосовує ходи так, як контракт, і повертає події для глядачів
+    fn play(
+        state: &mut GameState,
+        actions: &[GameAction],
+        timestamp_micros: u64,
+    ) -> Vec<GameEvent> {
+        actions
+            .iter()
+            .filter(|action| engine::apply_action(state, action, timestamp_micros).is_ok())
+            .map(|action| GameEvent::ActionApplied {
+                timestamp_micros,
+                action: action.clone(),
+            })
+            .collect()
+    }
+
+    // Розкопування до кінця гри; щоразу обирається хід, що очищає найбільше рядків
+    fn dig_race_events() -> (GameState, Vec<GameEvent>) {
+        dig_race_with(RuleSet::default())
+    }
+
+    fn dig_race_with(rules: RuleSet) -> (GameState, Vec<GameEvent>) {
+        let mut live = GameState {
+            mode: GameMode::DigRace,
+            seed: 11,
+            ..GameState::new(rules.clone())
+        };
+        engine::prepare_board(&mut live);
+        live.current_piece = engine::generate_new_piece(&mut live);
+
+        let mut events = vec![GameEvent::GameStarted {
+            seed: 11,
+            mode: GameMode::DigRace,
+            rules,
+        }];
+        let mut timestamp = 1_000_000;
+        while !live.game_over && live.piece_count < 500 {
+            timestamp += 100_000;
+            let best = (0..4)
+                .flat_map(|turns| (-5..=5).map(move |shift| placement(turns, shift)))
+                .max_by_key(|actions| {
+                    let mut trial = live.clone();
+                    play(&mut trial, actions, timestamp);
+                    trial.lines
+                })
+                .unwrap();
+            events.extend(play(&mut live, &best, timestamp));
+            events.push(GameEvent::AchievementEarned {
+                id: "first_clear".to_string(),
+            });
+        }
+        (live, events)
+    }
+
+    fn watch(watched: &mut WatchedGame, events: &[GameEvent]) {
+        for event in events {
+            apply_event(watched, event.clone());
+        }
+    }
+
+    fn same_state(left: &GameState, right: &GameState) -> bool {
+        bcs::to_bytes(left).unwrap() == bcs::to_bytes(right).unwrap()
+    }
+
+    #[test]
+    fn events_rebuild_the_game() {
+        let (live, events) = dig_race_events();
+        assert!(live.game_over && live.lines > 0);
+
+        let mut watched = WatchedGame::default();
+        watch(&mut watched, &events);
+        assert!(same_state(
+            &replay::simulate(&watched.replay).unwrap(),
+            &live
+        ));
+    }
+
+    #[test]
+    fn snapshot_catches_up_late_spectator() {
+        let (live, events) = dig_race_events();
+        let (early, late) = events.split_at(events.len() / 2);
+        let mut host = WatchedGame::default();
+        watch(&mut host, early);
+
+        let mut watched = WatchedGame::default();
+        apply_event(
+            &mut watched,
+            GameEvent::Snapshot {
+                replay: host.replay.to_bytes(),
+            },
+        );
+        let partial = replay::simulate(&watched.replay).unwrap();
+        assert!(!partial.game_over && partial.piece_count < live.piece_count);
+
+        watch(&mut watched, late);
+        assert!(same_state(
+            &replay::simulate(&watched.replay).unwrap(),
+            &live
+        ));
+
+        // Пошкоджений знімок не затирає вже отриманий запис
+        apply_event(&mut watched, GameEvent::Snapshot { replay: vec![0xff] });
+        assert!(same_state(
+            &replay::simulate(&watched.replay).unwrap(),
+            &live
+        ));
+    }
+
+    #[test]
+    fn hidden_games_are_streamed_as_visible_state() {
+        let rules = RuleSet {
+            visibility: Visibility::Invisible { after_pieces: 0 },
+            ..RuleSet::default()
+        };
+        let (live, events) = dig_race_with(rules);
+        let (early, _) = events.split_at(events.len() / 2);
+        let mut host = WatchedGame::default();
+        watch(&mut host, early);
+        let mut state = replay::simulate(&host.replay).unwrap();
+        assert!(!state.game_over);
+
+        // Поки гра триває, глядач не отримує ні запису, ні ходів
+        let mut watched = WatchedGame::default();
+        apply_event(&mut watched, snapshot_event(&state, &host.replay));
+        let action = GameEvent::ActionApplied {
+            timestamp_micros: 0,
+            action: GameAction::Drop,
+        };
+        apply_event(&mut watched, progress_event(&state, &host.replay, action));
+        assert!(watched.replay.entries.is_empty());
+        let seen = watched.state().unwrap();
+        assert_eq!(seen.board, visibility::visible_board(&state));
+        assert_ne!(seen.board, state.board);
+
+        // Після завершення гри глядач отримує весь запис
+        state.game_over = true;
+        let mut finished = WatchedGame::default();
+        watch(&mut finished, &events);
+        apply_event(
+            &mut watched,
+            progress_event(
+                &state,
+                &finished.replay,
+                GameEvent::PerfectClear { lines: 0, score: 0 },
+            ),
+        );
+        assert!(watched.visible.is_none());
+        assert!(same_state(
+            &watched.state().unwrap(),
+            &visibility::visible_state(&live)
+        ));
+    }
+}
@@ -0,0 +1,51 @@
+use linera_sdk::{
+    base::ChainId,
+    views::{linera_views, LogView, MapView, RegisterView, RootView, ViewStorageContext},
+};
+
+use crate::{
+    achievements::{AchievementProgress, EarnedAchievement},
+    challenge::{Challenge, ChallengeKey},
+    history::{GameRecord, GameStart, HistoryStats},
+    puzzle::PuzzleGoal,
+    replay::Replay,
+    royale::RoyaleMatch,
+    spectate::WatchedGame,
+    GameState,
+};
+
+#[derive(RootView)]
+#[view(context = "ViewStorageContext")]
+pub struct TetrisState {
+    pub game: RegisterView<GameState>,
+    // Запис поточної (або останньої) гри
+    pub replay: RegisterView<Replay>,
+    pub game_start: RegisterView<GameStart>,
+    // Час останньої дії гравця в поточній грі
+    pub last_activity: RegisterView<u64>,
+    // Завершені ігри цього гравця та їхні записи
+    pub history: LogView<GameRecord>,
+    pub history_stats: RegisterView<HistoryStats>,
+    pub archived_replays: MapView<u32, Vec<u8>>,
+    // Досягнення гравця цього ланцюжка та лічильники для них
+    pub achievements: MapView<String, EarnedAchievement>,
+    pub achievement_progress: RegisterView<AchievementProgress>,
+    // Координатор королівської битви на ланцюжку матчу
+    pub royale: RegisterView<Option<RoyaleMatch>>,
+    // Ланцюжок матчу, до якого гравець подав заявку
+    pub joined_royale: RegisterView<Option<ChainId>>,
+    // Турнір, для якого зіграно поточну гру
+    pub tournament_entry: RegisterView<Option<u64>>,
+    // Головоломка поточної гри та її мета
+    pub puzzle_entry: RegisterView<Option<(u64, PuzzleGoal)>>,
+    // Виклики від друзів і до друзів та виклик, для якого зіграно поточну гру
+    pub challenges: MapView<ChallengeKey, Challenge>,
+    pub next_challenge_id: RegisterView<u64>,
+    pub challenge_entry: RegisterView<Option<ChallengeKey>>,
+    // Перевірені записи суперників у викликах
+    pub challenge_replays: MapView<ChallengeKey, Vec<u8>>,
+    // Ланцюжки, яким надсилаються події цієї гри
+    pub spectators: RegisterView<Vec<ChainId>>,
+    // Ігри, які переглядає цей ланцюжок
+    pub watched: MapView<ChainId, WatchedGame>,
+}
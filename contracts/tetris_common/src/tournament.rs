@@ -0,0 +1,658 @@
+use linera_sdk::{
+    abis::fungible::{Account, FungibleTokenAbi},
+    base::{
+        AccountOwner, Amount, ApplicationId, BytecodeId, ChainId, ContractAbi, Owner, ServiceAbi,
+        Timestamp,
+    },
+    views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    prizes::{self, PrizeLedger},
+    replay::{self, Replay},
+    rules::RuleSet,
+    GameMode, TetrisContract, TetrisParameters,
+};
+
+pub struct TournamentAbi;
+
+impl ContractAbi for TournamentAbi {
+    type Operation = TournamentOperation;
+    type Response = TournamentResponse;
+}
+
+impl ServiceAbi for TournamentAbi {
+    type Query = TournamentQuery;
+    type QueryResponse = TournamentQueryResponse;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TournamentParameters {
+    pub tournament_chain: ChainId,
+    // Токен для внесків; без нього турніри лише безкоштовні
+    pub token: Option<ApplicationId<FungibleTokenAbi>>,
+    // Спроби починає та надсилає лише застосунок з кодом гри; сама гра знає
+    // застосунок турнірів, тож її ідентифікатор тут не потрібен
+    pub tetris_bytecode: BytecodeId<TetrisContract, TetrisParameters>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TournamentConfig {
+    pub mode: GameMode,
+    pub seed: u64,
+    // Правила, за якими мають бути зіграні спроби
+    pub rules: RuleSet,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub max_entrants: u32,
+    // 0 означає необмежену кількість спроб
+    pub max_attempts: u32,
+    pub entry_fee: Amount,
+    // Частки призового фонду у відсотках за місцями
+    pub prize_split: Vec<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TournamentOperation {
+    Create(TournamentConfig),
+    // Гравець погоджується сплатити вказаний внесок
+    Register {
+        tournament_id: u64,
+        entry_fee: Amount,
+    },
+    // Гравець почав гру з зерном турніру; спроба зараховується одразу
+    StartAttempt {
+        tournament_id: u64,
+    },
+    SubmitResult {
+        tournament_id: u64,
+        replay: Vec<u8>,
+    },
+    Finalize {
+        tournament_id: u64,
+    },
+    Cancel {
+        tournament_id: u64,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TournamentResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TournamentMessage {
+    Create(TournamentConfig),
+    Register {
+        tournament_id: u64,
+        fee_paid: Amount,
+        payer: Option<Owner>,
+    },
+    StartAttempt {
+        tournament_id: u64,
+    },
+    SubmitResult {
+        tournament_id: u64,
+        replay: Vec<u8>,
+    },
+    Finalize {
+        tournament_id: u64,
+    },
+    Cancel {
+        tournament_id: u64,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TournamentStatus {
+    Scheduled,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Entrant {
+    pub player: ChainId,
+    pub best_score: Option<u32>,
+    pub attempts: u32,
+    // Коли почалася спроба, результату якої ще чекаємо
+    pub attempt_started_at: Option<Timestamp>,
+    // Час, коли було встановлено найкращий результат
+    pub best_at: Option<Timestamp>,
+    // Власник рахунку, з якого сплачено внесок і куди йдуть виплати
+    pub payer: Option<Owner>,
+    pub fee_paid: Amount,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Standing {
+    pub rank: u32,
+    pub player: ChainId,
+    pub score: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Tournament {
+    pub id: u64,
+    pub organizer: ChainId,
+    pub config: TournamentConfig,
+    pub entrants: Vec<Entrant>,
+    // Остаточна таблиця, записана після завершення турніру
+    pub final_standings: Option<Vec<Standing>>,
+    pub prize_pool: Amount,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TournamentQuery {
+    Tournaments,
+    Tournament { tournament_id: u64 },
+    Standings { tournament_id: u64 },
+    // Найкраща спроба гравця, наприклад для гри з привидом
+    BestReplay { tournament_id: u64, player: ChainId },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum TournamentQueryResponse {
+    Tournaments(Vec<(Tournament, TournamentStatus)>),
+    Tournament(Option<Box<(Tournament, TournamentStatus)>>),
+    Standings(Vec<Standing>),
+    BestReplay(Option<Vec<u8>>),
+}
+
+#[derive(RootView)]
+#[view(context = "ViewStorageContext")]
+pub struct TournamentState {
+    pub tournaments: MapView<u64, Tournament>,
+    pub next_tournament_id: RegisterView<u64>,
+    // Турніри, які ще потрібно завершити
+    pub active: RegisterView<Vec<u64>>,
+    pub best_replays: MapView<(u64, ChainId), Vec<u8>>,
+}
+
+impl TournamentConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.start >= self.end {
+            return Err("Tournament must end after it starts");
+        }
+        if self.max_entrants == 0 {
+            return Err("Tournament needs at least one entrant");
+        }
+        if self.entry_fee > Amount::ZERO && self.prize_split.iter().sum::<u32>() != 100 {
+            return Err("Prize split must add up to 100 percent");
+        }
+        if self.mode.is_multiplayer() {
+            return Err("Tournament mode must be single-player");
+        }
+        self.rules.validate()?;
+        Ok(())
+    }
+}
+
+impl Tournament {
+    pub fn new(id: u64, organizer: ChainId, config: TournamentConfig) -> Self {
+        Self {
+            id,
+            organizer,
+            config,
+            entrants: Vec::new(),
+            final_standings: None,
+            prize_pool: Amount::ZERO,
+            cancelled: false,
+        }
+    }
+
+    pub fn status(&self, now: Timestamp) -> TournamentStatus {
+        if self.cancelled {
+            TournamentStatus::Cancelled
+        } else if self.final_standings.is_some() || now > self.config.end {
+            TournamentStatus::Finished
+        } else if now < self.config.start {
+            TournamentStatus::Scheduled
+        } else {
+            TournamentStatus::Running
+        }
+    }
+
+    pub fn register(
+        &mut self,
+        player: ChainId,
+        payer: Option<Owner>,
+        fee_paid: Amount,
+        now: Timestamp,
+    ) -> Result<(), &'static str> {
+        if matches!(
+            self.status(now),
+            TournamentStatus::Finished | TournamentStatus::Cancelled
+        ) {
+            return Err("Tournament is finished");
+        }
+        if fee_paid != self.config.entry_fee {
+            return Err("Wrong entry fee");
+        }
+        if fee_paid > Amount::ZERO && payer.is_none() {
+            return Err("Entry fee needs a payer");
+        }
+        if self.entrants.iter().any(|entrant| entrant.player == player) {
+            return Err("Already registered");
+        }
+        if self.entrants.len() as u32 >= self.config.max_entrants {
+            return Err("Tournament is full");
+        }
+
+        self.entrants.push(Entrant {
+            player,
+            best_score: None,
+            attempts: 0,
+            attempt_started_at: None,
+            best_at: None,
+            payer,
+            fee_paid,
+        });
+        self.prize_pool.saturating_add_assign(fee_paid);
+        Ok(())
+    }
+
+    // Зараховує спробу на початку гри, щоб зерно не можна було перегравати без ліку
+    pub fn start_attempt(&mut self, player: ChainId, now: Timestamp) -> Result<(), &'static str> {
+        if self.status(now) != TournamentStatus::Running {
+            return Err("Tournament is not running");
+        }
+
+        let max_attempts = self.config.max_attempts;
+        let entrant = self
+            .entrants
+            .iter_mut()
+            .find(|entrant| entrant.player == player)
+            .ok_or("Not registered")?;
+        if max_attempts > 0 && entrant.attempts >= max_attempts {
+            return Err("No attempts left");
+        }
+
+        entrant.attempts += 1;
+        entrant.attempt_started_at = Some(now);
+        Ok(())
+    }
+
+    // Перевіряє спробу повторною симуляцією; повертає, чи це новий найкращий результат.
+    // Вікно турніру перевіряється за часом ланцюжка турнірів, а не за часом у записі.
+    pub fn submit(
+        &mut self,
+        player: ChainId,
+        replay_bytes: &[u8],
+        now: Timestamp,
+    ) -> Result<bool, &'static str> {
+        if self.status(now) != TournamentStatus::Running {
+            return Err("Tournament is not running");
+        }
+
+        let config = &self.config;
+        let entrant = self
+            .entrants
+            .iter_mut()
+            .find(|entrant| entrant.player == player)
+            .ok_or("Not registered")?;
+        if entrant.attempt_started_at.take().is_none() {
+            return Err("No attempt in progress");
+        }
+
+        let replay = Replay::from_bytes(replay_bytes).map_err(|_| "Invalid replay")?;
+        if replay.seed != config.seed || replay.mode != config.mode || replay.rules != config.rules
+        {
+            return Err("Replay does not match tournament");
+        }
+
+        let score = replay::simulate(&replay)
+            .map_err(|_| "Replay verification failed")?
+            .score;
+
+        let improved = entrant.best_score.is_none_or(|best| score > best);
+        if improved {
+            entrant.best_score = Some(score);
+            entrant.best_at = Some(now);
+        }
+        Ok(improved)
+    }
+
+    // Поточна таблиця за рахунком; гравці з однаковим рахунком ділять місце
+    pub fn standings(&self) -> Vec<Standing> {
+        if let Some(standings) = &self.final_standings {
+            return standings.clone();
+        }
+
+        let mut scored: Vec<&Entrant> = self
+            .entrants
+            .iter()
+            .filter(|entrant| entrant.best_score.is_some())
+            .collect();
+        scored.sort_by_key(|entrant| (std::cmp::Reverse(entrant.best_score), entrant.best_at));
+
+        scored
+            .iter()
+            .map(|entrant| Standing {
+                rank: 1 + scored
+                    .iter()
+                    .filter(|other| other.best_score > entrant.best_score)
+                    .count() as u32,
+                player: entrant.player,
+                score: entrant.best_score.unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    // Завершує турнір після кінця вікна та виплачує призи
+    pub fn finalize(&mut self, now: Timestamp, ledger: &mut impl PrizeLedger) -> bool {
+        if self.cancelled || self.final_standings.is_some() || now <= self.config.end {
+            return false;
+        }
+
+        let standings = self.standings();
+        let shares = prizes::prize_shares(&standings, self.prize_pool, &self.config.prize_split);
+        self.final_standings = Some(standings);
+
+        if shares.is_empty() {
+            // Ніхто не зіграв: повертаємо внески
+            self.refund_all(ledger);
+            return true;
+        }
+        for (player, amount) in shares {
+            if let Some(account) = self.payout_account(player) {
+                ledger.transfer(amount, account);
+            }
+        }
+        self.prize_pool = Amount::ZERO;
+        true
+    }
+
+    // Скасування організатором повертає всі внески
+    pub fn cancel(&mut self, ledger: &mut impl PrizeLedger) -> Result<(), &'static str> {
+        if self.cancelled || self.final_standings.is_some() {
+            return Err("Tournament is already over");
+        }
+        self.cancelled = true;
+        self.refund_all(ledger);
+        Ok(())
+    }
+
+    fn refund_all(&mut self, ledger: &mut impl PrizeLedger) {
+        for entrant in &self.entrants {
+            refund(ledger, entrant.player, entrant.payer, entrant.fee_paid);
+        }
+        self.prize_pool = Amount::ZERO;
+    }
+
+    fn payout_account(&self, player: ChainId) -> Option<Account> {
+        let entrant = self
+            .entrants
+            .iter()
+            .find(|entrant| entrant.player == player)?;
+        Some(Account {
+            chain_id: player,
+            owner: AccountOwner::User(entrant.payer?),
+        })
+    }
+}
+
+// Повертає внесок гравцеві, наприклад коли реєстрацію відхилено
+pub fn refund(
+    ledger: &mut impl PrizeLedger,
+    player: ChainId,
+    payer: Option<Owner>,
+    amount: Amount,
+) {
+    if let Some(owner) = payer.filter(|_| amount > Amount::ZERO) {
+        ledger.transfer(
+            amount,
+            Account {
+                chain_id: player,
+                owner: AccountOwner::User(owner),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_sdk::base::CryptoHash;
+
+    use super::*;
+    use crate::prizes::LocalLedger;
+
+    const FEE: u128 = 10;
+
+    fn owner(index: u64) -> Owner {
+        Owner(CryptoHash::from([index, 0, 0, 0]))
+    }
+
+    fn account(index: u32) -> Account {
+        Account {
+            chain_id: ChainId::root(index),
+            owner: AccountOwner::User(owner(index as u64)),
+        }
+    }
+
+    fn paid_tournament(entrants: u32, ledger: &mut LocalLedger) -> Tournament {
+        let mut tournament = Tournament::new(
+            0,
+            ChainId::root(0),
+            TournamentConfig {
+                mode: GameMode::Marathon,
+                rules: RuleSet::default(),
+                seed: 7,
+                start: Timestamp::from(100),
+                end: Timestamp::from(200),
+                max_entrants: 8,
+                max_attempts: 0,
+                entry_fee: Amount::from_tokens(FEE),
+                prize_split: vec![50, 30, 20],
+            },
+        );
+        for index in 1..=entrants {
+            tournament
+                .register(
+                    ChainId::root(index),
+                    Some(owner(index as u64)),
+                    Amount::from_tokens(FEE),
+                    Timestamp::from(50),
+                )
+                .unwrap();
+            ledger.deposit(Amount::from_tokens(FEE));
+        }
+        tournament
+    }
+
+    fn set_score(tournament: &mut Tournament, index: u32, score: u32) {
+        let entrant = tournament
+            .entrants
+            .iter_mut()
+            .find(|entrant| entrant.player == ChainId::root(index))
+            .unwrap();
+        entrant.best_score = Some(score);
+        entrant.best_at = Some(Timestamp::from(150 + index as u64));
+    }
+
+    #[test]
+    fn prizes_follow_final_rank() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(4, &mut ledger);
+        set_score(&mut tournament, 1, 300);
+        set_score(&mut tournament, 2, 200);
+        set_score(&mut tournament, 3, 100);
+        set_score(&mut tournament, 4, 50);
+
+        assert!(!tournament.finalize(Timestamp::from(200), &mut ledger));
+        assert!(tournament.finalize(Timestamp::from(201), &mut ledger));
+        assert_eq!(ledger.balance(&account(1)), Amount::from_tokens(20));
+        assert_eq!(ledger.balance(&account(2)), Amount::from_tokens(12));
+        assert_eq!(ledger.balance(&account(3)), Amount::from_tokens(8));
+        assert_eq!(ledger.balance(&account(4)), Amount::ZERO);
+        assert_eq!(ledger.pool, Amount::ZERO);
+        assert_eq!(
+            tournament.status(Timestamp::from(300)),
+            TournamentStatus::Finished
+        );
+    }
+
+    #[test]
+    fn tied_players_share_prizes() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(4, &mut ledger);
+        set_score(&mut tournament, 1, 300);
+        set_score(&mut tournament, 2, 300);
+        set_score(&mut tournament, 3, 100);
+
+        let standings = tournament.standings();
+        assert_eq!(standings[0].rank, 1);
+        assert_eq!(standings[1].rank, 1);
+        assert_eq!(standings[2].rank, 3);
+
+        assert!(tournament.finalize(Timestamp::from(201), &mut ledger));
+        // Перше і друге місця (20 + 12) діляться навпіл
+        assert_eq!(ledger.balance(&account(1)), Amount::from_tokens(16));
+        assert_eq!(ledger.balance(&account(2)), Amount::from_tokens(16));
+        assert_eq!(ledger.balance(&account(3)), Amount::from_tokens(8));
+        assert_eq!(ledger.pool, Amount::ZERO);
+    }
+
+    #[test]
+    fn unfilled_places_are_redistributed() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(3, &mut ledger);
+        set_score(&mut tournament, 1, 300);
+        set_score(&mut tournament, 2, 200);
+
+        assert!(tournament.finalize(Timestamp::from(201), &mut ledger));
+        // 30 токенів діляться як 50:30
+        assert_eq!(
+            ledger.balance(&account(1)),
+            Amount::from_attos(18_750_000_000_000_000_000)
+        );
+        assert_eq!(
+            ledger.balance(&account(2)),
+            Amount::from_attos(11_250_000_000_000_000_000)
+        );
+        assert_eq!(ledger.pool, Amount::ZERO);
+    }
+
+    #[test]
+    fn fees_are_refunded_without_results() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(2, &mut ledger);
+
+        assert!(tournament.finalize(Timestamp::from(201), &mut ledger));
+        assert_eq!(ledger.balance(&account(1)), Amount::from_tokens(FEE));
+        assert_eq!(ledger.balance(&account(2)), Amount::from_tokens(FEE));
+        assert_eq!(ledger.pool, Amount::ZERO);
+    }
+
+    #[test]
+    fn cancelled_tournament_refunds_everyone() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(3, &mut ledger);
+        set_score(&mut tournament, 1, 300);
+
+        tournament.cancel(&mut ledger).unwrap();
+        for index in 1..=3 {
+            assert_eq!(ledger.balance(&account(index)), Amount::from_tokens(FEE));
+        }
+        assert_eq!(ledger.pool, Amount::ZERO);
+        assert_eq!(
+            tournament.status(Timestamp::from(150)),
+            TournamentStatus::Cancelled
+        );
+        assert!(tournament.cancel(&mut ledger).is_err());
+        assert!(!tournament.finalize(Timestamp::from(201), &mut ledger));
+    }
+
+    #[test]
+    fn rejected_registration_is_refunded() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(1, &mut ledger);
+
+        ledger.deposit(Amount::from_tokens(5));
+        let result = tournament.register(
+            ChainId::root(2),
+            Some(owner(2)),
+            Amount::from_tokens(5),
+            Timestamp::from(50),
+        );
+        assert_eq!(result, Err("Wrong entry fee"));
+        refund(
+            &mut ledger,
+            ChainId::root(2),
+            Some(owner(2)),
+            Amount::from_tokens(5),
+        );
+
+        assert_eq!(ledger.balance(&account(2)), Amount::from_tokens(5));
+        assert_eq!(ledger.pool, Amount::from_tokens(FEE));
+        assert_eq!(tournament.prize_pool, Amount::from_tokens(FEE));
+    }
+
+    fn attempt_replay(seed: u64) -> Vec<u8> {
+        let mut replay = Replay::new(seed, GameMode::Marathon, RuleSet::default());
+        replay.record_action(1, crate::GameAction::Drop);
+        replay.to_bytes()
+    }
+
+    #[test]
+    fn attempts_are_counted_when_started() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(1, &mut ledger);
+        tournament.config.max_attempts = 2;
+        let player = ChainId::root(1);
+
+        // Без розпочатої спроби результат не приймається
+        assert_eq!(
+            tournament.submit(player, &attempt_replay(7), Timestamp::from(150)),
+            Err("No attempt in progress")
+        );
+        assert_eq!(
+            tournament.start_attempt(player, Timestamp::from(50)),
+            Err("Tournament is not running")
+        );
+
+        tournament
+            .start_attempt(player, Timestamp::from(110))
+            .unwrap();
+        assert_eq!(
+            tournament.submit(player, &attempt_replay(8), Timestamp::from(120)),
+            Err("Replay does not match tournament")
+        );
+        // Невдала спроба теж витрачена
+        tournament
+            .start_attempt(player, Timestamp::from(130))
+            .unwrap();
+        assert_eq!(
+            tournament.start_attempt(player, Timestamp::from(140)),
+            Err("No attempts left")
+        );
+        assert_eq!(
+            tournament.submit(player, &attempt_replay(7), Timestamp::from(150)),
+            Ok(true)
+        );
+        assert_eq!(tournament.entrants[0].attempts, 2);
+        assert!(tournament.entrants[0].best_score.is_some());
+    }
+
+    #[test]
+    fn results_after_the_window_are_rejected() {
+        let mut ledger = LocalLedger::default();
+        let mut tournament = paid_tournament(1, &mut ledger);
+        let player = ChainId::root(1);
+
+        tournament
+            .start_attempt(player, Timestamp::from(190))
+            .unwrap();
+        // Час визначає ланцюжок турнірів, а не записані в спробі моменти
+        assert_eq!(
+            tournament.submit(player, &attempt_replay(7), Timestamp::from(201)),
+            Err("Tournament is not running")
+        );
+        assert!(tournament.entrants[0].best_score.is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{replay::Replay, rules::Visibility, GameState, PieceType};
+
+// Поле таким, яким його бачить гравець; після завершення гри видно все
+pub fn visible_board(state: &GameState) -> Vec<Vec<Option<PieceType>>> {
+    if state.game_over {
+        return state.board.clone();
+    }
+
+    match state.rules.visibility {
+        Visibility::Full => state.board.clone(),
+        Visibility::Invisible { after_pieces } => {
+            let Some(stamps) = &state.lock_stamps else {
+                return state.board.clone();
+            };
+            state
+                .board
+                .iter()
+                .zip(stamps)
+                .map(|(row, stamps)| {
+                    row.iter()
+                        .zip(stamps)
+                        .map(|(cell, stamp)| {
+                            cell.filter(|_| {
+                                state.piece_count.saturating_sub(*stamp) <= after_pieces as u32
+                            })
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+        Visibility::Fog { rows } => {
+            let top = state
+                .board
+                .iter()
+                .position(|row| row.iter().any(Option::is_some))
+                .unwrap_or(state.board.len());
+            state
+                .board
+                .iter()
+                .enumerate()
+                .map(|(y, row)| {
+                    if (top..top + rows as usize).contains(&y) {
+                        row.clone()
+                    } else {
+                        vec![None; row.len()]
+                    }
+                })
+                .collect()
+        }
+    }
+}
+
+// Стан гри для запитів: приховані клітинки порожні, а номери фігур не розкриваються
+pub fn visible_state(state: &GameState) -> GameState {
+    GameState {
+        board: visible_board(state),
+        lock_stamps: None,
+        ..state.clone()
+    }
+}
+
+// Запис поточної гри з прихованим полем віддаємо лише після її завершення:
+// інакше з нього можна відтворити все поле
+pub fn visible_replay(state: &GameState, replay: &Replay) -> Option<Vec<u8>> {
+    (state.game_over || state.rules.visibility == Visibility::Full).then(|| replay.to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine, replay, rules::RuleSet, GameAction, GameMode};
+
+    fn state_after_drops(visibility: Visibility, drops: usize) -> GameState {
+        let mut state = GameState::new(RuleSet {
+            visibility,
+            ..RuleSet::default()
+        });
+        state.current_piece = engine::generate_new_piece(&mut state);
+        for _ in 0..drops {
+            engine::apply_action(&mut state, &GameAction::Drop, 0).unwrap();
+        }
+        state
+    }
+
+    fn filled(board: &[Vec<Option<PieceType>>]) -> usize {
+        board.iter().flatten().filter(|cell| cell.is_some()).count()
+    }
+
+    #[test]
+    fn locked_blocks_fade_out() {
+        let invisible = Visibility::Invisible { after_pieces: 1 };
+        let state = state_after_drops(invisible, 1);
+        assert_eq!(filled(&visible_board(&state)), 4);
+
+        // Після другої фігури першої вже не видно, хоча вона й лишилася на полі
+        let mut state = state_after_drops(invisible, 2);
+        assert_eq!(filled(&visible_board(&state)), 4);
+        assert_eq!(filled(&state.board), 8);
+        assert!(visible_state(&state).lock_stamps.is_none());
+
+        state.game_over = true;
+        assert_eq!(visible_board(&state), state.board);
+    }
+
+    #[test]
+    fn fog_reveals_top_rows() {
+        let mut state = state_after_drops(Visibility::Fog { rows: 1 }, 0);
+        let width = state.rules.width as usize;
+        for row in state.board.iter_mut().rev().take(3) {
+            *row = vec![Some(PieceType::Garbage); width - 1];
+            row.push(None);
+        }
+
+        let board = visible_board(&state);
+        assert_eq!(filled(&board), width - 1);
+        assert!(board[board.len() - 3][0].is_some());
+    }
+
+    #[test]
+    fn hidden_game_cannot_be_rebuilt() {
+        let rules = RuleSet {
+            visibility: Visibility::Invisible { after_pieces: 1 },
+            ..RuleSet::default()
+        };
+        let mut recorded = Replay::new(7, GameMode::Marathon, rules);
+        for timestamp in 1..=3 {
+            recorded.record_action(timestamp * 1_000_000, GameAction::Drop);
+        }
+        let mut live = replay::simulate(&recorded).unwrap();
+        assert!(!live.game_over);
+        assert!(visible_replay(&live, &recorded).is_none());
+
+        // Привид з власного запису показує лише те, що бачить гравець
+        let ghost = visible_state(&replay::simulate_to_piece(&recorded, live.piece_count).unwrap());
+        assert_eq!(ghost.board, visible_board(&live));
+        assert!(filled(&ghost.board) < filled(&live.board));
+
+        live.game_over = true;
+        let bytes = visible_replay(&live, &recorded).unwrap();
+        let rebuilt = replay::simulate(&Replay::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(rebuilt.board, live.board);
+    }
+}
@@ -1,14 +1,30 @@
 use linera_sdk::{
-    base::{ContractAbi, WithContractAbi},
+    base::{ChainId, ContractAbi, WithContractAbi},
+    views::{RootView, View, ViewStorageContext},
     Contract, ContractRuntime,
 };
 use tetris_common::{
-    GameAction, GameResponse, GameState, Operation, Piece, PieceType, Position, TETROMINOES,
+    achievements::{self, AchievementContext, EarnedAchievement},
+    challenge::Challenge,
+    engine, garbage,
+    history::{EndReason, GameRecord, GameStart},
+    puzzle::{Puzzle, PuzzleOperation},
+    random,
+    rating::{MatchResult, RatingOperation},
+    replay::Replay,
+    royale::RoyaleMatch,
+    spectate::{self, GameEvent, WatchedGame, MAX_SPECTATORS},
+    tournament::TournamentOperation,
+    visibility, GameAction, GameMode, GameResponse, GameState, GarbageAttack, Message, Operation,
+    TetrisParameters, TetrisState, VersusResult,
 };
 
-#[derive(Clone)]
+const MAX_ROYALE_PLAYERS: u32 = 99;
+
 pub struct TetrisContractImpl {
     state: GameState,
+    storage: TetrisState,
+    runtime: ContractRuntime<Self>,
 }
 
 #[derive(Clone)]
@@ -26,283 +42,1100 @@ impl WithContractAbi for TetrisContractImpl {
 linera_sdk::contract!(TetrisContractImpl);
 
 impl Contract for TetrisContractImpl {
-    type Message = ();
-    type Parameters = ();
+    type Message = Message;
+    type Parameters = TetrisParameters;
     type InstantiationArgument = ();
 
-    async fn load(_runtime: ContractRuntime<Self>) -> Self {
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let storage = TetrisState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
         Self {
-            state: GameState::default(),
+            state: storage.game.get().clone(),
+            storage,
+            runtime,
         }
     }
 
     async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
-        // Нічого додаткового не потрібно робити під час створення екземпляра
+        // Перевіряємо лише правила гри; решта параметрів необов'язкова
+        self.runtime
+            .application_parameters()
+            .rules
+            .validate()
+            .expect("Invalid rule set");
     }
 
     async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
-        let response = match operation.action {
+        let abandoned = self.abandon_if_idle().await;
+
+        match operation.action {
+            GameAction::MoveLeft
+            | GameAction::MoveRight
+            | GameAction::Rotate
+            | GameAction::Drop
+            | GameAction::GameOver
+            | GameAction::Hold
+            | GameAction::Pause
+            | GameAction::Resume
+            | GameAction::Forfeit
+                if abandoned =>
+            {
+                GameResponse {
+                    success: false,
+                    message: "Game was abandoned after being idle".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
+                }
+            }
             GameAction::StartGame => {
+                let seed = self.runtime.system_time().micros();
+                self.start_solo(seed, GameMode::Marathon)
+            }
+            GameAction::StartSeeded {
+                mode,
+                seed,
+                tournament_id,
+            } => {
+                // Гра з наперед відомим зерном, наприклад для турніру
+                if mode.is_multiplayer() {
+                    return GameResponse {
+                        success: false,
+                        message: "Mode requires an opponent".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+                let response = self.start_solo(seed, mode);
+                if response.success {
+                    self.storage.tournament_entry.set(tournament_id);
+                    self.start_tournament_attempt();
+                }
+                response
+            }
+            GameAction::StartPuzzle { puzzle_id, puzzle } => {
+                self.start_puzzle(puzzle_id, *puzzle)
+            }
+            action @ (GameAction::MoveLeft
+            | GameAction::MoveRight
+            | GameAction::Rotate
+            | GameAction::Drop
+            | GameAction::GameOver
+            | GameAction::Hold
+            | GameAction::Pause
+            | GameAction::Resume) => {
+                let was_playing = !self.state.game_over && self.state.current_piece.is_some();
+                let timestamp_micros = self.runtime.system_time().micros();
+                let piece_count = self.state.piece_count;
+
+                if action == GameAction::Pause
+                    && !self.runtime.application_parameters().can_pause(&self.state)
+                {
+                    return GameResponse {
+                        success: false,
+                        message: "No pauses left".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+
+                match engine::apply_action(&mut self.state, &action, timestamp_micros) {
+                    Ok(result) => {
+                        if was_playing {
+                            self.record_action(timestamp_micros, action);
+                            self.storage.last_activity.set(timestamp_micros);
+                        }
+                        if result.attack > 0 {
+                            self.send_garbage(result.attack);
+                        }
+                        if result.t_spin {
+                            self.storage.achievement_progress.get_mut().t_spins += 1;
+                        }
+                        if result.perfect_clear {
+                            self.storage.achievement_progress.get_mut().perfect_clears += 1;
+                            self.publish(GameEvent::PerfectClear {
+                                lines: result.lines_cleared,
+                                score: self.state.score,
+                            });
+                        }
+                        // Фігура зафіксувалася скиданням або гравітацією
+                        if was_playing && self.state.piece_count != piece_count {
+                            self.check_achievements(result.largest_clear).await;
+                        }
+                        // Розв'язана головоломка завершується одразу
+                        let solved = was_playing
+                            && !self.state.game_over
+                            && self
+                                .storage
+                                .puzzle_entry
+                                .get()
+                                .is_some_and(|(_, goal)| goal.is_reached(&self.state, &result));
+                        if solved {
+                            self.stop_game();
+                        }
+                        if was_playing && self.state.game_over {
+                            self.finish_versus(VersusResult::Lost);
+                            self.archive_game(EndReason::Finished).await;
+                        }
+
+                        GameResponse {
+                            success: true,
+                            message: if solved {
+                                "Puzzle solved"
+                            } else {
+                                result.message
+                            }
+                            .to_string(),
+                            game_state: Some(visibility::visible_state(&self.state)),
+                        }
+                    }
+                    Err(message) => GameResponse {
+                        success: false,
+                        message: message.to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    },
+                }
+            }
+            GameAction::Forfeit => {
+                if self.state.game_over || self.state.current_piece.is_none() {
+                    return GameResponse {
+                        success: false,
+                        message: "Game not in progress".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+
+                self.stop_game();
+                self.finish_versus(VersusResult::Lost);
+                self.archive_game(EndReason::Forfeited).await;
+                GameResponse {
+                    success: true,
+                    message: "Game forfeited".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
+                }
+            }
+            GameAction::StartVersus { opponent_chain } => {
+                let seed = self.runtime.system_time().micros();
+                self.start_versus(opponent_chain, seed, None)
+            }
+            GameAction::MatchFound {
+                opponent_chain,
+                seed,
+                match_id,
+            } => {
+                if !self.called_by_lobby() {
+                    return GameResponse {
+                        success: false,
+                        message: "Matches are started by the lobby".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+                self.start_versus(opponent_chain, seed, Some(match_id))
+            }
+            GameAction::CancelMatch { match_id } => {
+                if !self.called_by_lobby() {
+                    return GameResponse {
+                        success: false,
+                        message: "Matches are cancelled by the lobby".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+                if self.state.game_over || self.state.match_id != Some(match_id) {
+                    return GameResponse {
+                        success: false,
+                        message: "Match is not in progress".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+
+                // Суперник так і не почав гру: без результату й рейтингу
+                self.stop_game();
+                self.archive_game(EndReason::Abandoned).await;
+                GameResponse {
+                    success: true,
+                    message: "Match cancelled".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
+                }
+            }
+            GameAction::CreateRoyale { max_players } => {
+                if self
+                    .storage
+                    .royale
+                    .get()
+                    .as_ref()
+                    .is_some_and(|royale| !royale.finished)
+                {
+                    return GameResponse {
+                        success: false,
+                        message: "Royale match already exists".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+
+                self.storage
+                    .royale
+                    .set(Some(RoyaleMatch::new(max_players.clamp(2, MAX_ROYALE_PLAYERS))));
+
+                GameResponse {
+                    success: true,
+                    message: "Royale match created".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
+                }
+            }
+            GameAction::JoinRoyale { match_chain } => {
                 if !self.state.game_over && self.state.current_piece.is_some() {
                     return GameResponse {
                         success: false,
                         message: "Game is already in progress".to_string(),
-                        game_state: Some(self.state.clone()),
+                        game_state: Some(visibility::visible_state(&self.state)),
                     };
                 }
 
-                // Ініціалізуємо нову гру
-                self.state = GameState::default();
-                self.state.current_piece = Some(generate_new_piece(&mut self.state));
+                self.storage.joined_royale.set(Some(match_chain));
+                self.runtime
+                    .prepare_message(Message::RoyaleJoin)
+                    .send_to(match_chain);
 
                 GameResponse {
                     success: true,
-                    message: "Game started".to_string(),
-                    game_state: Some(self.state.clone()),
+                    message: "Royale join requested".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
                 }
             }
-            GameAction::MoveLeft => {
-                if self.state.game_over || self.state.current_piece.is_none() {
+            GameAction::StartRoyale => {
+                let can_start = self
+                    .storage
+                    .royale
+                    .get()
+                    .as_ref()
+                    .is_some_and(|royale| !royale.started && royale.players.len() >= 2);
+                if !can_start {
                     return GameResponse {
                         success: false,
-                        message: "Game not in progress".to_string(),
-                        game_state: Some(self.state.clone()),
+                        message: "Royale match cannot be started".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
                     };
                 }
 
-                let mut updated_piece = self.state.current_piece.clone().unwrap();
-                updated_piece.position.x -= 1;
+                self.start_royale();
 
-                if is_valid_move(&self.state, &updated_piece) {
-                    self.state.current_piece = Some(updated_piece);
-                    GameResponse {
-                        success: true,
-                        message: "Moved left".to_string(),
-                        game_state: Some(self.state.clone()),
-                    }
-                } else {
-                    GameResponse {
-                        success: false,
-                        message: "Cannot move left".to_string(),
-                        game_state: Some(self.state.clone()),
-                    }
+                GameResponse {
+                    success: true,
+                    message: "Royale match started".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
                 }
             }
-            GameAction::MoveRight => {
-                if self.state.game_over || self.state.current_piece.is_none() {
+            GameAction::SetTargeting { strategy } => {
+                if self.state.mode != GameMode::Royale || self.state.game_over {
                     return GameResponse {
                         success: false,
-                        message: "Game not in progress".to_string(),
-                        game_state: Some(self.state.clone()),
+                        message: "Royale game not in progress".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
                     };
                 }
 
-                let mut updated_piece = self.state.current_piece.clone().unwrap();
-                updated_piece.position.x += 1;
+                if let Some(match_chain) = self.state.opponent_chain {
+                    self.runtime
+                        .prepare_message(Message::RoyaleTargeting { strategy })
+                        .send_to(match_chain);
+                }
 
-                if is_valid_move(&self.state, &updated_piece) {
-                    self.state.current_piece = Some(updated_piece);
-                    GameResponse {
-                        success: true,
-                        message: "Moved right".to_string(),
-                        game_state: Some(self.state.clone()),
-                    }
-                } else {
-                    GameResponse {
+                GameResponse {
+                    success: true,
+                    message: "Targeting updated".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
+                }
+            }
+            GameAction::Subscribe { player_chain } => {
+                if player_chain == self.runtime.chain_id() {
+                    return GameResponse {
                         success: false,
-                        message: "Cannot move right".to_string(),
-                        game_state: Some(self.state.clone()),
-                    }
+                        message: "Cannot spectate own chain".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                }
+
+                // Порожня гра заповниться знімком від гравця
+                self.storage
+                    .watched
+                    .insert(&player_chain, WatchedGame::default())
+                    .expect("Failed to store watched game");
+                self.runtime
+                    .prepare_message(Message::Subscribe)
+                    .send_to(player_chain);
+
+                GameResponse {
+                    success: true,
+                    message: "Subscribed".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
                 }
             }
-            GameAction::Rotate => {
-                if self.state.game_over || self.state.current_piece.is_none() {
+            GameAction::Unsubscribe { player_chain } => {
+                self.storage
+                    .watched
+                    .remove(&player_chain)
+                    .expect("Failed to remove watched game");
+                self.runtime
+                    .prepare_message(Message::Unsubscribe)
+                    .send_to(player_chain);
+
+                GameResponse {
+                    success: true,
+                    message: "Unsubscribed".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
+                }
+            }
+            GameAction::Challenge {
+                to_chain,
+                mode,
+                seed,
+            } => {
+                let chain_id = self.runtime.chain_id();
+                if to_chain == chain_id || mode.is_multiplayer() {
                     return GameResponse {
                         success: false,
-                        message: "Game not in progress".to_string(),
-                        game_state: Some(self.state.clone()),
+                        message: "Invalid challenge".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
                     };
                 }
 
-                let mut updated_piece = self.state.current_piece.clone().unwrap();
-                updated_piece.rotation = (updated_piece.rotation + 1) % 4;
+                let challenge_id = *self.storage.next_challenge_id.get();
+                self.storage.next_challenge_id.set(challenge_id + 1);
+                let challenge = Challenge {
+                    challenger: chain_id,
+                    id: challenge_id,
+                    opponent: to_chain,
+                    mode,
+                    seed,
+                    challenger_score: None,
+                    opponent_score: None,
+                    declined: false,
+                    rules: self.runtime.application_parameters().rules,
+                    created_at: self.runtime.system_time(),
+                };
+                self.storage
+                    .challenges
+                    .insert(&challenge.key(), challenge)
+                    .expect("Failed to store challenge");
+                self.runtime
+                    .prepare_message(Message::ChallengeIssued {
+                        challenge_id,
+                        mode,
+                        seed,
+                    })
+                    .send_to(to_chain);
 
-                if is_valid_move(&self.state, &updated_piece) {
-                    self.state.current_piece = Some(updated_piece);
-                    GameResponse {
-                        success: true,
-                        message: "Rotated".to_string(),
-                        game_state: Some(self.state.clone()),
-                    }
-                } else {
-                    GameResponse {
+                GameResponse {
+                    success: true,
+                    message: "Challenge sent".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
+                }
+            }
+            GameAction::PlayChallenge {
+                challenger,
+                challenge_id,
+            } => {
+                let chain_id = self.runtime.chain_id();
+                let now = self.runtime.system_time();
+                let challenge = self
+                    .storage
+                    .challenges
+                    .get(&(challenger, challenge_id))
+                    .await
+                    .expect("Failed to read challenge")
+                    .filter(|challenge| {
+                        !challenge.declined
+                            && !challenge.is_expired(now)
+                            && challenge.score_of(chain_id).is_none()
+                    });
+                let Some(challenge) = challenge else {
+                    return GameResponse {
                         success: false,
-                        message: "Cannot rotate".to_string(),
-                        game_state: Some(self.state.clone()),
-                    }
+                        message: "Challenge cannot be played".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
+                    };
+                };
+
+                // Обидва гравці грають ту саму послідовність фігур
+                let response = self.start_solo(challenge.seed, challenge.mode);
+                if response.success {
+                    self.storage.challenge_entry.set(Some(challenge.key()));
                 }
+                response
             }
-            GameAction::Drop => {
-                if self.state.game_over || self.state.current_piece.is_none() {
+            GameAction::DeclineChallenge {
+                challenger,
+                challenge_id,
+            } => {
+                let chain_id = self.runtime.chain_id();
+                let Some(challenge) = self
+                    .storage
+                    .challenges
+                    .get_mut(&(challenger, challenge_id))
+                    .await
+                    .expect("Failed to read challenge")
+                    .filter(|challenge| {
+                        challenge.opponent == chain_id && challenge.opponent_score.is_none()
+                    })
+                else {
                     return GameResponse {
                         success: false,
-                        message: "Game not in progress".to_string(),
-                        game_state: Some(self.state.clone()),
+                        message: "Challenge cannot be declined".to_string(),
+                        game_state: Some(visibility::visible_state(&self.state)),
                     };
+                };
+
+                challenge.declined = true;
+                self.runtime
+                    .prepare_message(Message::ChallengeDeclined { challenge_id })
+                    .send_to(challenger);
+
+                GameResponse {
+                    success: true,
+                    message: "Challenge declined".to_string(),
+                    game_state: Some(visibility::visible_state(&self.state)),
                 }
+            }
+        }
+    }
 
-                let mut current_piece = self.state.current_piece.clone().unwrap();
-                
-                // Опускаємо фігуру вниз, поки це можливо
-                while is_valid_move(&self.state, &Piece {
-                    position: Position { x: current_piece.position.x, y: current_piece.position.y + 1 },
-                    ..current_piece.clone()
-                }) {
-                    current_piece.position.y += 1;
-                }
-                
-                // Розміщуємо фігуру на дошці
-                place_piece(&mut self.state, &current_piece);
-                
-                // Перевіряємо та очищаємо заповнені рядки
-                clear_lines(&mut self.state);
-                
-                // Створюємо нову фігуру
-                let new_piece = generate_new_piece(&mut self.state);
-                
-                // Перевіряємо, чи можна розмістити нову фігуру
-                if !is_valid_move(&self.state, &new_piece) {
-                    self.state.game_over = true;
-                    self.state.current_piece = None;
-                    
-                    GameResponse {
-                        success: true,
-                        message: "Game over".to_string(),
-                        game_state: Some(self.state.clone()),
-                    }
+    async fn execute_message(&mut self, message: Self::Message) {
+        let sender = self
+            .runtime
+            .message_id()
+            .expect("Message must have an ID")
+            .chain_id;
+        // Повідомлення гри приймаємо лише від поточного суперника (або координатора)
+        let from_opponent = engine::is_multiplayer(&self.state)
+            && !self.state.game_over
+            && self.state.opponent_chain == Some(sender);
+
+        match message {
+            Message::SendGarbage { lines, hole_column } => {
+                if from_opponent {
+                    let attack = GarbageAttack { lines, hole_column };
+                    garbage::queue_garbage(&mut self.state, attack);
+                    let timestamp_micros = self.runtime.system_time().micros();
+                    self.record_garbage(timestamp_micros, attack);
+                }
+            }
+            Message::ToppedOut => {
+                // Суперник програв, тож ця гра завершується перемогою
+                if from_opponent && self.state.mode == GameMode::Versus {
+                    self.stop_game();
+                    self.finish_versus(VersusResult::Won);
+                    self.archive_game(EndReason::Finished).await;
+                }
+            }
+            Message::RoyaleJoin => {
+                let Some(mut royale) = self.storage.royale.get().clone() else {
+                    return;
+                };
+                if royale.join(sender) && royale.is_full() {
+                    self.storage.royale.set(Some(royale));
+                    self.start_royale();
                 } else {
-                    self.state.current_piece = Some(new_piece);
-                    
-                    GameResponse {
-                        success: true,
-                        message: "Piece dropped".to_string(),
-                        game_state: Some(self.state.clone()),
+                    self.storage.royale.set(Some(royale));
+                }
+            }
+            Message::RoyaleStarted { seed } => {
+                if *self.storage.joined_royale.get() != Some(sender) {
+                    return;
+                }
+                self.storage.joined_royale.set(None);
+
+                // Поточну гру не перериваємо: гравець вибуває, і координатор не чекатиме на нього
+                if !self.state.game_over && self.state.current_piece.is_some() {
+                    self.runtime
+                        .prepare_message(Message::RoyaleToppedOut)
+                        .send_to(sender);
+                    return;
+                }
+
+                // Починаємо нову гру з координатором матчу як "суперником"
+                self.state = GameState {
+                    mode: GameMode::Royale,
+                    opponent_chain: Some(sender),
+                    seed,
+                    ..GameState::new(self.runtime.application_parameters().rules)
+                };
+                self.state.current_piece = engine::generate_new_piece(&mut self.state);
+                self.start_replay(seed, GameMode::Royale);
+            }
+            Message::RoyaleAttack { lines } => {
+                let Some(mut royale) = self.storage.royale.get().clone() else {
+                    return;
+                };
+                if !royale.started || royale.finished {
+                    return;
+                }
+
+                let lines = royale.boosted_lines(sender, lines);
+                let seed = self.runtime.system_time().micros() ^ lines as u64;
+                if let Some(target) = royale.choose_target(sender, seed) {
+                    let width = self.runtime.application_parameters().rules.width;
+                    let hole_column = (random::next_seed(seed) % width as u64) as u8;
+                    self.runtime
+                        .prepare_message(Message::SendGarbage { lines, hole_column })
+                        .send_to(target);
+                }
+                self.storage.royale.set(Some(royale));
+            }
+            Message::RoyaleToppedOut => self.knock_out_royale_player(sender),
+            Message::RoyaleTargeting { strategy } => {
+                if let Some(royale) = self.storage.royale.get_mut() {
+                    royale.set_strategy(sender, strategy);
+                }
+            }
+            Message::RoyalePlacement { placement, .. } => {
+                if self.state.mode != GameMode::Royale || self.state.opponent_chain != Some(sender)
+                {
+                    return;
+                }
+
+                self.state.placement = Some(placement);
+                // Останній гравець, що залишився, перемагає
+                if placement == 1 && !self.state.game_over {
+                    self.stop_game();
+                    self.finish_versus(VersusResult::Won);
+                    self.archive_game(EndReason::Finished).await;
+                }
+            }
+            Message::Subscribe => {
+                let spectators = self.storage.spectators.get_mut();
+                if spectators.contains(&sender) || spectators.len() >= MAX_SPECTATORS {
+                    return;
+                }
+                spectators.push(sender);
+
+                // Новий глядач отримує запис гри від її початку, а гру з прихованим полем
+                // до завершення бачить лише такою, якою її бачить гравець
+                let snapshot = spectate::snapshot_event(&self.state, self.storage.replay.get());
+                self.runtime
+                    .prepare_message(Message::GameEvent(snapshot))
+                    .send_to(sender);
+            }
+            Message::Unsubscribe => {
+                self.storage
+                    .spectators
+                    .get_mut()
+                    .retain(|spectator| *spectator != sender);
+            }
+            Message::GameEvent(event) => {
+                // Події приймаємо лише від гравців, яких переглядаємо
+                if let Some(watched) = self
+                    .storage
+                    .watched
+                    .get_mut(&sender)
+                    .await
+                    .expect("Failed to read watched game")
+                {
+                    spectate::apply_event(watched, event);
+                }
+            }
+            Message::ChallengeIssued {
+                challenge_id,
+                mode,
+                seed,
+            } => {
+                let key = (sender, challenge_id);
+                if mode.is_multiplayer()
+                    || self
+                        .storage
+                        .challenges
+                        .contains_key(&key)
+                        .await
+                        .expect("Failed to read challenge")
+                {
+                    return;
+                }
+
+                let challenge = Challenge {
+                    challenger: sender,
+                    id: challenge_id,
+                    opponent: self.runtime.chain_id(),
+                    mode,
+                    seed,
+                    challenger_score: None,
+                    opponent_score: None,
+                    declined: false,
+                    rules: self.runtime.application_parameters().rules,
+                    created_at: self.runtime.system_time(),
+                };
+                self.storage
+                    .challenges
+                    .insert(&key, challenge)
+                    .expect("Failed to store challenge");
+            }
+            Message::ChallengeResult {
+                challenger,
+                challenge_id,
+                replay,
+            } => {
+                // Рахунок суперника приймаємо лише після перевірки його запису
+                let now = self.runtime.system_time();
+                if let Some(challenge) = self
+                    .storage
+                    .challenges
+                    .get_mut(&(challenger, challenge_id))
+                    .await
+                    .expect("Failed to read challenge")
+                {
+                    if challenge.is_participant(sender) && challenge.score_of(sender).is_none() {
+                        if let Ok(score) = challenge.verify_result(&replay, now) {
+                            challenge.record_score(sender, score);
+                            self.storage
+                                .challenge_replays
+                                .insert(&(challenger, challenge_id), replay)
+                                .expect("Failed to store challenge replay");
+                        }
                     }
                 }
             }
-            GameAction::GameOver => {
-                self.state.game_over = true;
-                self.state.current_piece = None;
-                
-                GameResponse {
-                    success: true,
-                    message: "Game over".to_string(),
-                    game_state: Some(self.state.clone()),
+            Message::ChallengeDeclined { challenge_id } => {
+                let chain_id = self.runtime.chain_id();
+                if let Some(challenge) = self
+                    .storage
+                    .challenges
+                    .get_mut(&(chain_id, challenge_id))
+                    .await
+                    .expect("Failed to read challenge")
+                {
+                    if challenge.opponent == sender {
+                        challenge.declined = true;
+                    }
                 }
             }
+        }
+    }
+
+    async fn store(mut self) {
+        // Зберігаємо стан після кожної транзакції
+        self.storage.game.set(self.state);
+        self.storage.save().await.expect("Failed to save state");
+    }
+}
+
+impl TetrisContractImpl {
+    fn start_solo(&mut self, seed: u64, mode: GameMode) -> GameResponse {
+        if !self.state.game_over && self.state.current_piece.is_some() {
+            return GameResponse {
+                success: false,
+                message: "Game is already in progress".to_string(),
+                game_state: Some(visibility::visible_state(&self.state)),
+            };
+        }
+
+        // Ініціалізуємо нову гру
+        self.state = GameState {
+            mode,
+            seed,
+            ..GameState::new(self.runtime.application_parameters().rules)
         };
+        engine::prepare_board(&mut self.state);
+        self.state.current_piece = engine::generate_new_piece(&mut self.state);
+        self.start_replay(seed, mode);
 
-        // Зберігаємо стан після кожної операції
-        self.clone().store().await;
-        response
+        GameResponse {
+            success: true,
+            message: "Game started".to_string(),
+            game_state: Some(visibility::visible_state(&self.state)),
+        }
     }
 
-    async fn execute_message(&mut self, _message: Self::Message) {
-        // Наразі повідомлення не використовуються
+    fn start_puzzle(&mut self, puzzle_id: u64, puzzle: Puzzle) -> GameResponse {
+        if !self.state.game_over && self.state.current_piece.is_some() {
+            return GameResponse {
+                success: false,
+                message: "Game is already in progress".to_string(),
+                game_state: Some(visibility::visible_state(&self.state)),
+            };
+        }
+        if let Err(error) = puzzle.validate() {
+            return GameResponse {
+                success: false,
+                message: error.to_string(),
+                game_state: Some(visibility::visible_state(&self.state)),
+            };
+        }
+
+        // Головоломка починається з дошки автора та має власні правила
+        self.state = puzzle.initial_state();
+        self.start_replay(0, GameMode::Puzzle);
+        self.storage.puzzle_entry.set(Some((puzzle_id, puzzle.goal)));
+
+        GameResponse {
+            success: true,
+            message: "Puzzle started".to_string(),
+            game_state: Some(visibility::visible_state(&self.state)),
+        }
     }
 
-    async fn store(self) {
-        // Зберігаємо стан (автоматично)
+    fn start_versus(
+        &mut self,
+        opponent_chain: ChainId,
+        seed: u64,
+        match_id: Option<u64>,
+    ) -> GameResponse {
+        if !self.state.game_over && self.state.current_piece.is_some() {
+            return GameResponse {
+                success: false,
+                message: "Game is already in progress".to_string(),
+                game_state: Some(visibility::visible_state(&self.state)),
+            };
+        }
+
+        if opponent_chain == self.runtime.chain_id() {
+            return GameResponse {
+                success: false,
+                message: "Cannot play versus against own chain".to_string(),
+                game_state: Some(visibility::visible_state(&self.state)),
+            };
+        }
+
+        // Ініціалізуємо нову гру проти суперника
+        self.state = GameState {
+            mode: GameMode::Versus,
+            opponent_chain: Some(opponent_chain),
+            seed,
+            match_id,
+            ..GameState::new(self.runtime.application_parameters().rules)
+        };
+        self.state.current_piece = engine::generate_new_piece(&mut self.state);
+        self.start_replay(seed, GameMode::Versus);
+
+        GameResponse {
+            success: true,
+            message: "Versus game started".to_string(),
+            game_state: Some(visibility::visible_state(&self.state)),
+        }
     }
-}
 
-fn generate_new_piece(state: &mut GameState) -> Piece {
-    // Використовуємо хеш стану для детермінованої генерації
-    let hash = state.score as usize;
-    let piece_index = hash % TETROMINOES.len();
-    let (piece_type, _) = TETROMINOES[piece_index];
-    
-    Piece {
-        piece_type,
-        position: Position { x: 4, y: 0 },
-        rotation: 0,
+    // Рейтингові матчі приходять лише автентифікованим викликом від застосунку з кодом лобі
+    fn called_by_lobby(&mut self) -> bool {
+        let lobby_bytecode = self.runtime.application_parameters().lobby_bytecode;
+        let caller = self.runtime.authenticated_caller_id();
+        lobby_bytecode.is_some_and(|bytecode| {
+            caller.is_some_and(|caller| caller.bytecode_id == bytecode.forget_abi())
+        })
     }
-}
 
-fn is_valid_move_with_board(board: &[[Option<PieceType>; 10]; 20], piece: &Piece) -> bool {
-    let (_piece_type, shape) = TETROMINOES
-        .iter()
-        .find(|(pt, _)| *pt == piece.piece_type)
-        .unwrap();
-    
-    let rotated_shape = tetris_common::rotate_shape(shape, piece.rotation);
-    
-    for (y, row) in rotated_shape.iter().enumerate() {
-        for (x, &cell) in row.iter().enumerate() {
-            if !cell {
-                continue; // Пропускаємо порожні клітини фігури
-            }
-            
-            let board_x = piece.position.x + x as i32;
-            let board_y = piece.position.y + y as i32;
-            
-            // Перевіряємо межі дошки
-            if board_x < 0 || board_x >= 10 || board_y < 0 || board_y >= 20 {
-                return false;
-            }
-            
-            // Перевіряємо колізії
-            if board[board_y as usize][board_x as usize].is_some() {
-                return false;
+    // Гру завершено не на дошці (повідомленням з іншого ланцюжка, здачею чи через простій);
+    // записуємо це як кінець гри
+    fn stop_game(&mut self) {
+        let timestamp_micros = self.runtime.system_time().micros();
+        self.state.game_over = true;
+        self.state.current_piece = None;
+        self.state.paused_at = None;
+        self.record_action(timestamp_micros, GameAction::GameOver);
+    }
+
+    // Покидає гру, якщо гравець надто довго нічого не робив або не повернувся з паузи
+    async fn abandon_if_idle(&mut self) -> bool {
+        let now = self.runtime.system_time().micros();
+        let last_activity = *self.storage.last_activity.get();
+        if !self
+            .runtime
+            .application_parameters()
+            .is_abandoned(&self.state, last_activity, now)
+        {
+            return false;
+        }
+
+        self.stop_game();
+        self.finish_versus(VersusResult::Lost);
+        self.archive_game(EndReason::Abandoned).await;
+        true
+    }
+
+    // Починає новий запис гри та повідомляє глядачів
+    fn start_replay(&mut self, seed: u64, mode: GameMode) {
+        let start = GameStart {
+            timestamp: self.runtime.system_time(),
+            block_height: self.runtime.block_height(),
+        };
+        self.storage.game_start.set(start);
+        self.storage.last_activity.set(start.timestamp.micros());
+        self.storage.tournament_entry.set(None);
+        self.storage.challenge_entry.set(None);
+        self.storage.puzzle_entry.set(None);
+        let rules = self.state.rules.clone();
+        self.storage.replay.set(Replay::new(seed, mode, rules.clone()));
+        self.publish(GameEvent::GameStarted { seed, mode, rules });
+    }
+
+    fn record_action(&mut self, timestamp_micros: u64, action: GameAction) {
+        self.storage
+            .replay
+            .get_mut()
+            .record_action(timestamp_micros, action.clone());
+        self.publish_progress(GameEvent::ActionApplied {
+            timestamp_micros,
+            action,
+        });
+    }
+
+    fn record_garbage(&mut self, timestamp_micros: u64, attack: GarbageAttack) {
+        self.storage
+            .replay
+            .get_mut()
+            .record_garbage(timestamp_micros, attack);
+        self.publish_progress(GameEvent::GarbageReceived {
+            timestamp_micros,
+            attack,
+        });
+    }
+
+    // Турнір зараховує спробу, щойно гра з його зерном почалася
+    fn start_tournament_attempt(&mut self) {
+        let tournament_application = self.runtime.application_parameters().tournament_application;
+        if let (Some(tournament_id), Some(application)) =
+            (*self.storage.tournament_entry.get(), tournament_application)
+        {
+            self.runtime.call_application(
+                true,
+                application,
+                &TournamentOperation::StartAttempt { tournament_id },
+            );
+        }
+    }
+
+    // Додає завершену гру до історії гравця разом з її записом
+    async fn archive_game(&mut self, end_reason: EndReason) {
+        let replay_id = self.storage.history.count() as u32;
+        let record = GameRecord::new(
+            &self.state,
+            *self.storage.game_start.get(),
+            self.runtime.system_time(),
+            self.runtime.block_height(),
+            end_reason,
+            replay_id,
+        );
+
+        self.storage
+            .archived_replays
+            .insert(&replay_id, self.storage.replay.get().to_bytes())
+            .expect("Failed to store replay");
+        self.storage.history_stats.get_mut().add(&record);
+        self.storage.history.push(record);
+
+        // Турнірна спроба автоматично надсилається на перевірку
+        let tournament_application = self.runtime.application_parameters().tournament_application;
+        if let (Some(tournament_id), Some(application)) =
+            (self.storage.tournament_entry.get(), tournament_application)
+        {
+            self.runtime.call_application(
+                true,
+                application,
+                &TournamentOperation::SubmitResult {
+                    tournament_id: *tournament_id,
+                    replay: self.storage.replay.get().to_bytes(),
+                },
+            );
+        }
+        self.storage.tournament_entry.set(None);
+
+        // Спроба головоломки так само надсилається на перевірку
+        let puzzle_application = self.runtime.application_parameters().puzzle_application;
+        if let (Some((puzzle_id, _)), Some(application)) =
+            (*self.storage.puzzle_entry.get(), puzzle_application)
+        {
+            self.runtime.call_application(
+                false,
+                application,
+                &PuzzleOperation::SubmitAttempt {
+                    puzzle_id,
+                    replay: self.storage.replay.get().to_bytes(),
+                },
+            );
+        }
+        self.storage.puzzle_entry.set(None);
+
+        // Завершені ігри на час потрапляють до таблиць рекордів
+        let rating_application = self.runtime.application_parameters().rating_application;
+        let timed = self.state.mode.is_timed() && end_reason == EndReason::Finished;
+        if let Some(application) = rating_application.filter(|_| timed) {
+            self.runtime.call_application(
+                true,
+                application,
+                &RatingOperation::SubmitRecord {
+                    replay: self.storage.replay.get().to_bytes(),
+                },
+            );
+        }
+
+        // Результат виклику разом із записом гри надсилається суперникові
+        if let Some(key) = *self.storage.challenge_entry.get() {
+            let chain_id = self.runtime.chain_id();
+            if let Some(challenge) = self
+                .storage
+                .challenges
+                .get_mut(&key)
+                .await
+                .expect("Failed to read challenge")
+            {
+                challenge.record_score(chain_id, self.state.score);
+                let (challenger, challenge_id) = key;
+                self.runtime
+                    .prepare_message(Message::ChallengeResult {
+                        challenger,
+                        challenge_id,
+                        replay: self.storage.replay.get().to_bytes(),
+                    })
+                    .send_to(challenge.other(chain_id));
             }
         }
+        self.storage.challenge_entry.set(None);
+
+        self.storage
+            .achievement_progress
+            .get_mut()
+            .record_game(&self.state);
+        self.check_achievements(0).await;
     }
-    
-    true
-}
 
-fn is_valid_move(state: &GameState, piece: &Piece) -> bool {
-    is_valid_move_with_board(&state.board, piece)
-}
+    // Зберігає нові досягнення та повідомляє про них глядачів
+    async fn check_achievements(&mut self, lines_cleared: u32) {
+        let timestamp = self.runtime.system_time();
+        let block_height = self.runtime.block_height();
+        let start = self.storage.game_start.get().timestamp;
+        let met = achievements::met_achievements(&AchievementContext {
+            state: &self.state,
+            lines_cleared,
+            elapsed_micros: timestamp.delta_since(start).as_micros(),
+            progress: self.storage.achievement_progress.get(),
+        });
 
-fn place_piece(state: &mut GameState, piece: &Piece) {
-    let (_piece_type, shape) = TETROMINOES
-        .iter()
-        .find(|(pt, _)| *pt == piece.piece_type)
-        .unwrap();
-    
-    let rotated_shape = tetris_common::rotate_shape(shape, piece.rotation);
-    
-    for (y, row) in rotated_shape.iter().enumerate() {
-        for (x, &cell) in row.iter().enumerate() {
-            if cell {
-                let board_x = piece.position.x + x as i32;
-                let board_y = piece.position.y + y as i32;
-                state.board[board_y as usize][board_x as usize] = Some(piece.piece_type);
+        for id in met {
+            if self
+                .storage
+                .achievements
+                .contains_key(&id)
+                .await
+                .expect("Failed to read achievements")
+            {
+                continue;
             }
+
+            let earned = EarnedAchievement {
+                id: id.clone(),
+                block_height,
+                timestamp,
+            };
+            self.storage
+                .achievements
+                .insert(&id, earned)
+                .expect("Failed to store achievement");
+            self.publish(GameEvent::AchievementEarned { id });
         }
     }
-}
 
-fn clear_lines(state: &mut GameState) {
-    let mut lines_cleared = 0;
-    let mut y = 19;
-    
-    while y > 0 {
-        if state.board[y].iter().all(|cell| cell.is_some()) {
-            // Зсуваємо всі рядки вище вниз
-            for y2 in (1..=y).rev() {
-                state.board[y2] = state.board[y2 - 1];
-            }
-            // Очищаємо верхній рядок
-            state.board[0] = [None; 10];
-            lines_cleared += 1;
-            // Не змінюємо y, щоб перевірити той самий рядок знову
-        } else {
-            y -= 1;
+    // Надсилає подію гри всім глядачам
+    fn publish(&mut self, event: GameEvent) {
+        for spectator in self.storage.spectators.get().clone() {
+            self.runtime
+                .prepare_message(Message::GameEvent(event.clone()))
+                .send_to(spectator);
         }
     }
-    
-    // Оновлюємо рахунок
-    state.score += lines_cleared * 100;
-} 
\ No newline at end of file
+
+    // Надсилає глядачам хід чи сміття, не розкриваючи приховане поле
+    fn publish_progress(&mut self, event: GameEvent) {
+        if self.storage.spectators.get().is_empty() {
+            return;
+        }
+        let event = spectate::progress_event(&self.state, self.storage.replay.get(), event);
+        self.publish(event);
+    }
+
+    fn start_royale(&mut self) {
+        let Some(royale) = self.storage.royale.get_mut() else {
+            return;
+        };
+        royale.started = true;
+
+        // Усі гравці отримують однакове зерно, тож і однакові фігури
+        let seed = self.runtime.system_time().micros();
+        let players: Vec<ChainId> = royale.players.iter().map(|player| player.chain_id).collect();
+        for chain_id in players {
+            self.runtime
+                .prepare_message(Message::RoyaleStarted { seed })
+                .send_to(chain_id);
+        }
+    }
+
+    fn knock_out_royale_player(&mut self, player: ChainId) {
+        let Some(royale) = self.storage.royale.get_mut() else {
+            return;
+        };
+        if !royale.started || royale.finished {
+            return;
+        }
+
+        for placement in royale.knock_out(player) {
+            self.runtime
+                .prepare_message(Message::RoyalePlacement {
+                    placement: placement.placement,
+                    badges: placement.badges,
+                })
+                .send_to(placement.chain_id);
+        }
+    }
+
+    fn finish_versus(&mut self, result: VersusResult) {
+        if !engine::is_multiplayer(&self.state) || self.state.versus_result.is_some() {
+            return;
+        }
+        let Some(opponent_chain) = self.state.opponent_chain else {
+            return;
+        };
+
+        self.state.versus_result = Some(result);
+        if result == VersusResult::Lost {
+            let message = match self.state.mode {
+                GameMode::Royale => Message::RoyaleToppedOut,
+                _ => Message::ToppedOut,
+            };
+            self.runtime.prepare_message(message).send_to(opponent_chain);
+        }
+
+        // Рейтингові матчі (з лобі) обидва гравці звітують на ланцюжок рейтингу
+        let rating_application = self.runtime.application_parameters().rating_application;
+        if let (Some(match_id), Some(rating_application)) =
+            (self.state.match_id, rating_application)
+        {
+            let own_chain = self.runtime.chain_id();
+            let (winner, loser) = match result {
+                VersusResult::Won => (own_chain, opponent_chain),
+                VersusResult::Lost => (opponent_chain, own_chain),
+            };
+            self.runtime.call_application(
+                false,
+                rating_application,
+                &RatingOperation::ReportResult(MatchResult {
+                    match_id,
+                    winner,
+                    loser,
+                }),
+            );
+        }
+    }
+
+    fn send_garbage(&mut self, lines: u32) {
+        let Some(opponent_chain) = self.state.opponent_chain else {
+            return;
+        };
+
+        // У королівській битві ціль обирає координатор матчу
+        if self.state.mode == GameMode::Royale {
+            self.runtime
+                .prepare_message(Message::RoyaleAttack { lines })
+                .send_to(opponent_chain);
+            return;
+        }
+
+        // Позиція отвору залежить від часу блоку, тому однакова для всіх валідаторів
+        let width = self.state.rules.width as u64;
+        let hole_column = (self.runtime.system_time().micros() % width) as u8;
+        self.runtime
+            .prepare_message(Message::SendGarbage { lines, hole_column })
+            .send_to(opponent_chain);
+    }
+}
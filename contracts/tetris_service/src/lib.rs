@@ -1,19 +1,26 @@
 use linera_sdk::{
     base::{ServiceAbi, WithServiceAbi},
+    views::{View, ViewStorageContext},
     Service, ServiceRuntime,
 };
-use tetris_common::GameState;
+use tetris_common::{
+    achievements::{self, AchievementStatus},
+    history,
+    replay::{self, Replay},
+    visibility, GhostReference, Query, QueryResponse, TetrisParameters, TetrisState,
+};
 
 pub struct TetrisServiceImpl {
-    state: GameState,
+    storage: TetrisState,
+    runtime: ServiceRuntime<Self>,
 }
 
 #[derive(Clone)]
 pub struct TetrisServiceAbi;
 
 impl ServiceAbi for TetrisServiceAbi {
-    type Query = ();
-    type QueryResponse = GameState;
+    type Query = Query;
+    type QueryResponse = QueryResponse;
 }
 
 impl WithServiceAbi for TetrisServiceImpl {
@@ -23,15 +30,147 @@ impl WithServiceAbi for TetrisServiceImpl {
 linera_sdk::service!(TetrisServiceImpl);
 
 impl Service for TetrisServiceImpl {
-    type Parameters = ();
+    type Parameters = TetrisParameters;
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let storage = TetrisState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { storage, runtime }
+    }
+
+    async fn handle_query(&self, query: Self::Query) -> Self::QueryResponse {
+        match query {
+            // Поле розкривається лише настільки, наскільки дозволяють правила гри
+            Query::GameState => QueryResponse::GameState(Box::new(visibility::visible_state(
+                self.storage.game.get(),
+            ))),
+            Query::Replay => QueryResponse::Replay(visibility::visible_replay(
+                self.storage.game.get(),
+                self.storage.replay.get(),
+            )),
+            Query::VerifyReplay {
+                replay,
+                claimed_score,
+            } => {
+                // Відтворюємо гру з отриманого запису і порівнюємо рахунок
+                let score = Replay::from_bytes(&replay)
+                    .and_then(|replay| replay::simulate(&replay))
+                    .ok()
+                    .map(|state| state.score);
+                QueryResponse::Verification {
+                    valid: score == Some(claimed_score),
+                    score,
+                }
+            }
+            Query::Spectate { player_chain } => {
+                let state = self
+                    .storage
+                    .watched
+                    .get(&player_chain)
+                    .await
+                    .expect("Failed to read watched game")
+                    .and_then(|watched| watched.state());
+                QueryResponse::Spectate(state.map(Box::new))
+            }
+            Query::History { offset, limit } => {
+                let total = self.storage.history.count() as u32;
+                let mut records = self
+                    .storage
+                    .history
+                    .read(history::page_range(total, offset, limit))
+                    .await
+                    .expect("Failed to read history");
+                records.reverse();
+                QueryResponse::History { total, records }
+            }
+            Query::HistoryStats => QueryResponse::HistoryStats(*self.storage.history_stats.get()),
+            Query::ArchivedReplay { replay_id } => QueryResponse::ArchivedReplay(
+                self.storage
+                    .archived_replays
+                    .get(&replay_id)
+                    .await
+                    .expect("Failed to read replay"),
+            ),
+            Query::Achievements => {
+                let mut statuses = Vec::new();
+                for definition in achievements::definitions() {
+                    let earned = self
+                        .storage
+                        .achievements
+                        .get(&definition.id)
+                        .await
+                        .expect("Failed to read achievements");
+                    statuses.push(AchievementStatus { definition, earned });
+                }
+                QueryResponse::Achievements(statuses)
+            }
+            Query::Challenges => {
+                let chain_id = self.runtime.chain_id();
+                let mut challenges = Vec::new();
+                self.storage
+                    .challenges
+                    .for_each_index_value(|_, challenge| {
+                        let outcome = challenge.outcome(chain_id);
+                        challenges.push((challenge, outcome));
+                        Ok(())
+                    })
+                    .await
+                    .expect("Failed to read challenges");
+                QueryResponse::Challenges(challenges)
+            }
+            Query::Ghost {
+                reference,
+                piece_count,
+            } => {
+                let piece_count =
+                    piece_count.unwrap_or_else(|| self.storage.game.get().piece_count);
+                let ghost = self
+                    .ghost_replay(reference)
+                    .await
+                    .and_then(|bytes| Replay::from_bytes(&bytes).ok())
+                    .and_then(|replay| replay::simulate_to_piece(&replay, piece_count).ok());
+                // Привид гри з прихованим полем фільтруємо так само, як і саму гру
+                QueryResponse::Ghost(ghost.map(|ghost| Box::new(visibility::visible_state(&ghost))))
+            }
+        }
+    }
+}
 
-    async fn new(_runtime: ServiceRuntime<Self>) -> Self {
-        Self {
-            state: GameState::default(),
+impl TetrisServiceImpl {
+    async fn ghost_replay(&self, reference: GhostReference) -> Option<Vec<u8>> {
+        match reference {
+            GhostReference::PersonalBest | GhostReference::Archived { .. } => {
+                let game = self.storage.game.get();
+                let count = self.storage.history.count();
+                let records = self
+                    .storage
+                    .history
+                    .read(0..count)
+                    .await
+                    .expect("Failed to read history");
+                let replay_id =
+                    history::ghost_replay_id(&records, &reference, game.seed, game.mode)?;
+                self.archived_replay(replay_id).await
+            }
+            GhostReference::Challenge {
+                challenger,
+                challenge_id,
+            } => self
+                .storage
+                .challenge_replays
+                .get(&(challenger, challenge_id))
+                .await
+                .expect("Failed to read challenge replay"),
+            GhostReference::Replay { replay } => Some(replay),
         }
     }
 
-    async fn handle_query(&self, _query: Self::Query) -> Self::QueryResponse {
-        self.state.clone()
+    async fn archived_replay(&self, replay_id: u32) -> Option<Vec<u8>> {
+        self.storage
+            .archived_replays
+            .get(&replay_id)
+            .await
+            .expect("Failed to read replay")
     }
-} 
\ No newline at end of file
+}
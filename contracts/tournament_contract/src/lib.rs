@@ -0,0 +1,314 @@
+use linera_sdk::{
+    abis::fungible::{self, Account, FungibleTokenAbi},
+    base::{AccountOwner, Amount, ApplicationId, WithContractAbi},
+    views::{RootView, View, ViewStorageContext},
+    Contract, ContractRuntime,
+};
+use tetris_common::{
+    prizes::PrizeLedger,
+    tournament::{
+        self, Tournament, TournamentAbi, TournamentMessage, TournamentOperation,
+        TournamentParameters, TournamentResponse, TournamentState,
+    },
+};
+
+pub struct TournamentContractImpl {
+    state: TournamentState,
+    runtime: ContractRuntime<Self>,
+}
+
+// Виплати з рахунку застосунку у фунгібельному токені
+struct FungibleLedger<'a> {
+    runtime: &'a mut ContractRuntime<TournamentContractImpl>,
+    token: Option<ApplicationId<FungibleTokenAbi>>,
+}
+
+impl PrizeLedger for FungibleLedger<'_> {
+    fn transfer(&mut self, amount: Amount, target_account: Account) {
+        let token = self.token.expect("Token application is not configured");
+        let owner = AccountOwner::Application(self.runtime.application_id().forget_abi());
+        self.runtime.call_application(
+            true,
+            token,
+            &fungible::Operation::Transfer {
+                owner,
+                amount,
+                target_account,
+            },
+        );
+    }
+}
+
+impl WithContractAbi for TournamentContractImpl {
+    type Abi = TournamentAbi;
+}
+
+linera_sdk::contract!(TournamentContractImpl);
+
+impl Contract for TournamentContractImpl {
+    type Message = TournamentMessage;
+    type Parameters = TournamentParameters;
+    type InstantiationArgument = ();
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = TournamentState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state, runtime }
+    }
+
+    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {}
+
+    async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
+        let parameters = self.runtime.application_parameters();
+
+        // Усі дії пересилаються на ланцюжок турнірів від імені цього ланцюжка
+        let message = match operation {
+            TournamentOperation::Create(config) => {
+                if let Err(error) = config.validate() {
+                    return TournamentResponse {
+                        success: false,
+                        message: error.to_string(),
+                    };
+                }
+                TournamentMessage::Create(config)
+            }
+            TournamentOperation::Register {
+                tournament_id,
+                entry_fee,
+            } => {
+                let mut payer = None;
+                if entry_fee > Amount::ZERO {
+                    let Some(token) = parameters.token else {
+                        return TournamentResponse {
+                            success: false,
+                            message: "Entry fees are not enabled".to_string(),
+                        };
+                    };
+                    let Some(owner) = self.runtime.authenticated_signer() else {
+                        return TournamentResponse {
+                            success: false,
+                            message: "Entry fee requires a signed operation".to_string(),
+                        };
+                    };
+
+                    // Внесок переходить на рахунок застосунку на ланцюжку турнірів
+                    let target_account = Account {
+                        chain_id: parameters.tournament_chain,
+                        owner: AccountOwner::Application(
+                            self.runtime.application_id().forget_abi(),
+                        ),
+                    };
+                    self.runtime.call_application(
+                        true,
+                        token,
+                        &fungible::Operation::Transfer {
+                            owner: AccountOwner::User(owner),
+                            amount: entry_fee,
+                            target_account,
+                        },
+                    );
+                    payer = Some(owner);
+                }
+
+                TournamentMessage::Register {
+                    tournament_id,
+                    fee_paid: entry_fee,
+                    payer,
+                }
+            }
+            // Спроби надходять лише автентифікованим викликом від застосунку гри,
+            // тож запис зіграно саме на цьому ланцюжку
+            TournamentOperation::StartAttempt { tournament_id } => {
+                if !self.called_by_tetris(&parameters) {
+                    return Self::not_from_game();
+                }
+                TournamentMessage::StartAttempt { tournament_id }
+            }
+            TournamentOperation::SubmitResult {
+                tournament_id,
+                replay,
+            } => {
+                if !self.called_by_tetris(&parameters) {
+                    return Self::not_from_game();
+                }
+                TournamentMessage::SubmitResult {
+                    tournament_id,
+                    replay,
+                }
+            }
+            TournamentOperation::Finalize { tournament_id } => {
+                TournamentMessage::Finalize { tournament_id }
+            }
+            TournamentOperation::Cancel { tournament_id } => {
+                TournamentMessage::Cancel { tournament_id }
+            }
+        };
+
+        self.runtime
+            .prepare_message(message)
+            .send_to(parameters.tournament_chain);
+
+        TournamentResponse {
+            success: true,
+            message: "Request sent".to_string(),
+        }
+    }
+
+    async fn execute_message(&mut self, message: Self::Message) {
+        let parameters = self.runtime.application_parameters();
+        if self.runtime.chain_id() != parameters.tournament_chain {
+            return;
+        }
+
+        let sender = self
+            .runtime
+            .message_id()
+            .expect("Message must have an ID")
+            .chain_id;
+        let now = self.runtime.system_time();
+
+        // Турніри, час яких минув, завершуються автоматично
+        self.finalize_expired(&parameters).await;
+
+        match message {
+            TournamentMessage::Create(config) => {
+                if config.validate().is_err()
+                    || (config.entry_fee > Amount::ZERO && parameters.token.is_none())
+                {
+                    return;
+                }
+                let id = *self.state.next_tournament_id.get();
+                self.state.next_tournament_id.set(id + 1);
+                self.state
+                    .tournaments
+                    .insert(&id, Tournament::new(id, sender, config))
+                    .expect("Failed to store tournament");
+                self.state.active.get_mut().push(id);
+            }
+            TournamentMessage::Register {
+                tournament_id,
+                fee_paid,
+                payer,
+            } => {
+                let registered = match self.tournament_mut(tournament_id).await {
+                    Some(tournament) => tournament.register(sender, payer, fee_paid, now).is_ok(),
+                    None => false,
+                };
+                // Відхилена реєстрація повертає сплачений внесок
+                if !registered {
+                    let mut ledger = FungibleLedger {
+                        runtime: &mut self.runtime,
+                        token: parameters.token,
+                    };
+                    tournament::refund(&mut ledger, sender, payer, fee_paid);
+                }
+            }
+            TournamentMessage::StartAttempt { tournament_id } => {
+                if let Some(tournament) = self.tournament_mut(tournament_id).await {
+                    // Спроба без реєстрації чи понад ліміт не зараховується,
+                    // і її результат потім буде відхилено
+                    let _ = tournament.start_attempt(sender, now);
+                }
+            }
+            TournamentMessage::SubmitResult {
+                tournament_id,
+                replay,
+            } => {
+                // Некоректні спроби просто ігноруються
+                let improved = match self.tournament_mut(tournament_id).await {
+                    Some(tournament) => tournament.submit(sender, &replay, now) == Ok(true),
+                    None => false,
+                };
+                if improved {
+                    self.state
+                        .best_replays
+                        .insert(&(tournament_id, sender), replay)
+                        .expect("Failed to store replay");
+                }
+            }
+            // Завершення вже виконано вище, якщо турнір закінчився
+            TournamentMessage::Finalize { .. } => {}
+            TournamentMessage::Cancel { tournament_id } => {
+                let Some(tournament) = self
+                    .state
+                    .tournaments
+                    .get_mut(&tournament_id)
+                    .await
+                    .expect("Failed to read tournament")
+                else {
+                    return;
+                };
+                if tournament.organizer != sender {
+                    return;
+                }
+
+                let mut ledger = FungibleLedger {
+                    runtime: &mut self.runtime,
+                    token: parameters.token,
+                };
+                if tournament.cancel(&mut ledger).is_ok() {
+                    self.state
+                        .active
+                        .get_mut()
+                        .retain(|active_id| *active_id != tournament_id);
+                }
+            }
+        }
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl TournamentContractImpl {
+    fn called_by_tetris(&mut self, parameters: &TournamentParameters) -> bool {
+        let tetris_bytecode = parameters.tetris_bytecode.forget_abi();
+        self.runtime
+            .authenticated_caller_id()
+            .is_some_and(|caller| caller.bytecode_id == tetris_bytecode)
+    }
+
+    fn not_from_game() -> TournamentResponse {
+        TournamentResponse {
+            success: false,
+            message: "Attempts are submitted by the game application".to_string(),
+        }
+    }
+
+    async fn tournament_mut(&mut self, tournament_id: u64) -> Option<&mut Tournament> {
+        self.state
+            .tournaments
+            .get_mut(&tournament_id)
+            .await
+            .expect("Failed to read tournament")
+    }
+
+    async fn finalize_expired(&mut self, parameters: &TournamentParameters) {
+        let now = self.runtime.system_time();
+        let mut still_active: Vec<u64> = Vec::new();
+
+        for tournament_id in self.state.active.get().clone() {
+            let Some(tournament) = self
+                .state
+                .tournaments
+                .get_mut(&tournament_id)
+                .await
+                .expect("Failed to read tournament")
+            else {
+                continue;
+            };
+
+            let mut ledger = FungibleLedger {
+                runtime: &mut self.runtime,
+                token: parameters.token,
+            };
+            if !tournament.finalize(now, &mut ledger) {
+                still_active.push(tournament_id);
+            }
+        }
+
+        self.state.active.set(still_active);
+    }
+}
@@ -0,0 +1,80 @@
+use linera_sdk::{
+    base::WithServiceAbi,
+    views::{View, ViewStorageContext},
+    Service, ServiceRuntime,
+};
+use tetris_common::tournament::{
+    TournamentAbi, TournamentParameters, TournamentQuery, TournamentQueryResponse, TournamentState,
+};
+
+pub struct TournamentServiceImpl {
+    state: TournamentState,
+    runtime: ServiceRuntime<Self>,
+}
+
+impl WithServiceAbi for TournamentServiceImpl {
+    type Abi = TournamentAbi;
+}
+
+linera_sdk::service!(TournamentServiceImpl);
+
+impl Service for TournamentServiceImpl {
+    type Parameters = TournamentParameters;
+
+    async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = TournamentState::load(ViewStorageContext::from(runtime.key_value_store()))
+            .await
+            .expect("Failed to load state");
+        Self { state, runtime }
+    }
+
+    async fn handle_query(&self, query: Self::Query) -> Self::QueryResponse {
+        let now = self.runtime.system_time();
+
+        match query {
+            TournamentQuery::Tournaments => {
+                let mut tournaments = Vec::new();
+                self.state
+                    .tournaments
+                    .for_each_index_value(|_, tournament| {
+                        let status = tournament.status(now);
+                        tournaments.push((tournament, status));
+                        Ok(())
+                    })
+                    .await
+                    .expect("Failed to read tournaments");
+                TournamentQueryResponse::Tournaments(tournaments)
+            }
+            TournamentQuery::Tournament { tournament_id } => TournamentQueryResponse::Tournament(
+                self.state
+                    .tournaments
+                    .get(&tournament_id)
+                    .await
+                    .expect("Failed to read tournament")
+                    .map(|tournament| {
+                        let status = tournament.status(now);
+                        Box::new((tournament, status))
+                    }),
+            ),
+            TournamentQuery::Standings { tournament_id } => TournamentQueryResponse::Standings(
+                self.state
+                    .tournaments
+                    .get(&tournament_id)
+                    .await
+                    .expect("Failed to read tournament")
+                    .map(|tournament| tournament.standings())
+                    .unwrap_or_default(),
+            ),
+            TournamentQuery::BestReplay {
+                tournament_id,
+                player,
+            } => TournamentQueryResponse::BestReplay(
+                self.state
+                    .best_replays
+                    .get(&(tournament_id, player))
+                    .await
+                    .expect("Failed to read replay"),
+            ),
+        }
+    }
+}